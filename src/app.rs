@@ -1,82 +1,511 @@
-use termion::event::Key;
+use std::{
+    path::Path,
+    process::{Command, Stdio},
+    rc::Rc,
+    thread,
+    time::{Duration, Instant},
+};
+
+use clap::ValueEnum;
+use termion::event::{Event, Key};
 
 use crate::{
-    input::{self, Action},
+    archive::Query,
+    export::{self, ExportFormat},
+    graphics::ImageProtocol,
+    hooks::Hooks,
+    input::{self, Action, KeyParser},
     renderer::Renderer,
-    scrape,
-    state::{Mode, State},
+    scrape::Article,
+    source::Sources,
+    state::{Layout, Mode, SortOrder, State},
+    store::{Bookmarks, ReadHistory},
+    util::TimeFormat,
+    watchlist::Watchlist,
 };
 
+/// How often relative publication times are updated.
+const TIME_UPDATE_INTERVAL: Duration = Duration::from_secs(30);
+
+/// How often an open liveblog is checked for new entries.
+const LIVEBLOG_POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+/// How long the selection has to stay on an article before the preview
+/// fetches it, so moving through the list doesn't wait on every article.
+const PREVIEW_DELAY: Duration = Duration::from_millis(300);
+
+/// How often images that are being fetched are checked for, to show them.
+const IMAGE_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// How long there has to be no input before article bodies are fetched to
+/// check them against the watchlist, one at a time.
+const WATCH_CHECK_DELAY: Duration = Duration::from_secs(1);
+
 pub struct App {
     renderer: Renderer<'static>,
     state: State,
+    key_parser: KeyParser,
+    categories: Vec<String>,
+    sources: Rc<Sources>,
+    refresh_interval: Option<Duration>,
+    last_refresh: Instant,
+    last_time_update: Instant,
+    last_liveblog_poll: Instant,
+    last_input: Instant,
+    save_bookmark_bodies: bool,
+    /// Command that videos and audio are opened with
+    player: String,
+    hooks: Hooks,
 }
 
 impl App {
-    pub fn new(url: Option<String>) -> Self {
-        let link = url.unwrap_or(String::from("https://nos.nl/nieuws/laatste"));
-        let articles = scrape::get_items(link).expect("Couldn't get article titles.");
+    pub fn new(
+        categories: Vec<String>,
+        sources: Sources,
+        refresh_interval: Option<Duration>,
+        time_format: TimeFormat,
+        image_protocol: ImageProtocol,
+        save_bookmark_bodies: bool,
+        player: String,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let categories = if categories.is_empty() {
+            vec![String::from("laatste")]
+        } else {
+            categories
+        };
+        let bookmarks = Bookmarks::load()?;
+        let sources = Rc::new(sources);
+        let listing = sources
+            .get_all_items(&categories)
+            .expect("Couldn't get article titles.");
 
         let mut renderer = Renderer::new();
-        let state = State::new(articles);
+        let mut state = State::new(
+            listing.articles,
+            Rc::clone(&sources),
+            ReadHistory::load(),
+            bookmarks,
+        );
+        state.add_next_pages(listing.next_pages);
+        state.set_time_format(time_format);
+        state.set_image_protocol(image_protocol.detect());
+        let key_parser = KeyParser::new();
 
         renderer.hide_cursor();
 
-        App { renderer, state }
+        Ok(App {
+            renderer,
+            state,
+            key_parser,
+            categories,
+            sources,
+            refresh_interval,
+            last_refresh: Instant::now(),
+            last_time_update: Instant::now(),
+            last_liveblog_poll: Instant::now(),
+            last_input: Instant::now(),
+            save_bookmark_bodies,
+            player,
+            hooks: Hooks::default(),
+        })
     }
 
     pub fn main(&mut self) {
-        let subset_titles = self.state.get_subset().to_owned();
-
-        if self.state.mode == Mode::Select {
-            self.renderer
-                .print_titles(&subset_titles, self.state.get_relative_row());
-        }
+        self.render();
 
         loop {
-            let keystroke = self.renderer.get_keystroke();
-            let action = input::handle_input(keystroke);
+            let event = match self.next_timeout() {
+                Some(timeout) => match self.renderer.get_event_timeout(timeout) {
+                    Some(event) => event,
+                    None => {
+                        self.on_timeout();
+                        continue;
+                    }
+                },
+                None => self.renderer.get_event(),
+            };
+            self.last_input = Instant::now();
+
+            let (action, count) = match event {
+                Event::Key(keystroke) => match self.key_parser.feed(keystroke) {
+                    Some(parsed) => parsed,
+                    None => {
+                        self.renderer
+                            .write_pending_keys(&self.key_parser.pending_keys());
+                        continue;
+                    }
+                },
+                Event::Mouse(mouse_event) => {
+                    self.key_parser.clear();
+                    (input::handle_mouse(mouse_event), None)
+                }
+                Event::Unsupported(_) => continue,
+            };
+            if count.is_some() {
+                self.renderer.clear_status_bar();
+            }
+            let repeat = count.unwrap_or(1);
 
             match action {
                 Action::Quit => break,
-                Action::MoveUp => self.state.move_up(),
-                Action::MoveDown => self.state.move_down(),
-                Action::GotoTop => self.state.go_top(),
-                Action::GotoBottom => self.state.go_bottom(),
-                Action::PageUp => self.state.page_up(),
-                Action::PageDown => self.state.page_down(),
+                Action::MoveUp => (0..repeat).for_each(|_| self.state.move_up()),
+                Action::MoveDown => (0..repeat).for_each(|_| self.state.move_down()),
+                Action::GotoTop => match count {
+                    Some(line) => self.state.go_to_row(line - 1),
+                    None => self.state.go_top(),
+                },
+                Action::GotoBottom => match count {
+                    Some(line) => self.state.go_to_row(line - 1),
+                    None => self.state.go_bottom(),
+                },
+                Action::PageUp => (0..repeat).for_each(|_| self.state.page_up()),
+                Action::PageDown => (0..repeat).for_each(|_| self.state.page_down()),
+                Action::HalfPageUp => (0..repeat).for_each(|_| self.state.half_page_up()),
+                Action::HalfPageDown => (0..repeat).for_each(|_| self.state.half_page_down()),
+                Action::CenterScreen => self.state.center_screen(),
+                Action::ScrollToTop => self.state.scroll_to_top(),
+                Action::ScrollToBottom => self.state.scroll_to_bottom(),
+                Action::ScreenTop => self.state.screen_top(repeat),
+                Action::ScreenMiddle => self.state.screen_middle(),
+                Action::ScreenBottom => self.state.screen_bottom(repeat),
+                Action::ScrollUp => self.state.scroll_up(input::SCROLL_LINES),
+                Action::ScrollDown => self.state.scroll_down(input::SCROLL_LINES),
+                Action::Click(column, row) => self.click(column, row),
                 Action::EnterArticle => self.enter_article(),
                 Action::GoBack => self.state.go_back(),
                 Action::Search => self.search(),
                 Action::Reset => self.state.reset(),
+                Action::Refresh => self.refresh(),
+                Action::ToggleHideRead => self.state.toggle_hide_read(),
+                Action::ToggleBookmark => self.toggle_bookmark(),
+                Action::PlayMedia => self.play_media(repeat),
+                Action::ToggleFocus => self.toggle_focus(),
+                Action::NextTab => match count {
+                    Some(tab) => self.state.go_to_tab(tab - 1),
+                    None => self.state.next_tab(true, 1),
+                },
+                Action::PreviousTab => self.state.next_tab(false, repeat),
                 // TODO: command mode (help, statusbar, etc.)
                 // TODO: command/search history, arrows to go through it
                 Action::CommandMode => self.command_mode(),
-                // TODO: center screen (vim zz)
-                _ => continue,
+                Action::None => {
+                    self.renderer.clear_status_bar();
+                    continue;
+                }
+            }
+            if self.state.can_load_more() {
+                self.load_more();
             }
-            match self.state.mode {
-                Mode::Select => {
-                    let subset_titles = self.state.get_subset().to_owned();
-                    let relative_selected_row = self.state.get_relative_row();
+            self.render();
+        }
+        self.renderer.clear_all();
+        self.renderer.show_cursor();
+    }
+
+    fn render(&mut self) {
+        self.state.update_preview();
+        self.state.receive_images();
+        self.renderer.set_top(self.state.tab_bar_rows());
+        if self.state.is_split() {
+            let subset_titles = self.state.get_list_subset().to_owned();
+            let title_styles = self.state.get_title_styles();
+            let relative_selected_row = self.state.get_relative_row();
+            self.renderer.print_titles(
+                &subset_titles,
+                &title_styles,
+                relative_selected_row,
+                self.state.mode == Mode::Select,
+            );
+            let subset_article = self.state.get_article_subset().to_owned();
+            let images = self.state.get_visible_images();
+            self.renderer
+                .print_preview(self.state.list_width(), &subset_article, &images);
+            self.write_tab_bar();
+            self.write_watch_alerts();
+            return;
+        }
+        match self.state.mode {
+            Mode::Select => {
+                let subset_titles = self.state.get_subset().to_owned();
+                let title_styles = self.state.get_title_styles();
+                let relative_selected_row = self.state.get_relative_row();
+                self.renderer.print_titles(
+                    &subset_titles,
+                    &title_styles,
+                    relative_selected_row,
+                    true,
+                );
+            }
+            Mode::Article => {
+                let subset_article = self.state.get_subset().to_owned();
+                let images = self.state.get_visible_images();
+                self.renderer.print_article(&subset_article, &images);
+            }
+        }
+        self.write_tab_bar();
+        self.write_watch_alerts();
+    }
+
+    fn write_tab_bar(&mut self) {
+        if self.state.tab_bar_rows() > 0 {
+            let (titles, current) = self.state.tab_titles();
+            self.renderer
+                .print_tab_bar(&titles, current, self.state.mode == Mode::Article);
+        }
+    }
+
+    fn write_watch_alerts(&mut self) {
+        let alerts = self.state.watch_alert_count();
+        if alerts > 0 {
+            self.renderer.write_watch_alerts(alerts);
+        }
+    }
+
+    pub fn set_layout(&mut self, layout: Layout, ratio: f64) {
+        self.state.set_layout(layout, ratio);
+    }
+
+    pub fn set_watchlist(&mut self, watchlist: Watchlist) {
+        self.state.set_watchlist(watchlist);
+    }
+
+    /// How long to wait for input before something else needs to happen, if at all.
+    fn next_timeout(&self) -> Option<Duration> {
+        let mut timeouts = Vec::new();
+
+        if self.key_parser.is_pending() {
+            timeouts.push(input::SEQUENCE_TIMEOUT);
+        }
+        if let Some(interval) = self.refresh_interval {
+            timeouts.push(interval.saturating_sub(self.last_refresh.elapsed()));
+        }
+        if self.state.get_time_format() == TimeFormat::Relative {
+            timeouts.push(TIME_UPDATE_INTERVAL.saturating_sub(self.last_time_update.elapsed()));
+        }
+        if self.state.is_liveblog_open() {
+            timeouts.push(LIVEBLOG_POLL_INTERVAL.saturating_sub(self.last_liveblog_poll.elapsed()));
+        }
+        if self.state.preview_pending() {
+            timeouts.push(PREVIEW_DELAY.saturating_sub(self.last_input.elapsed()));
+        }
+        if self.state.images_loading() {
+            timeouts.push(IMAGE_POLL_INTERVAL);
+        }
+        if self.state.watchlist_pending() {
+            timeouts.push(WATCH_CHECK_DELAY.saturating_sub(self.last_input.elapsed()));
+        }
+
+        timeouts.into_iter().min()
+    }
+
+    fn on_timeout(&mut self) {
+        if self.key_parser.is_pending() {
+            self.key_parser.clear();
+            self.renderer.clear_status_bar();
+        }
+        if let Some(interval) = self.refresh_interval
+            && self.last_refresh.elapsed() >= interval
+        {
+            self.refresh();
+            self.render();
+        }
+        if self.state.get_time_format() == TimeFormat::Relative
+            && self.last_time_update.elapsed() >= TIME_UPDATE_INTERVAL
+        {
+            self.last_time_update = Instant::now();
+            if self.state.mode == Mode::Select {
+                self.state.update_titles();
+                self.render();
+            }
+        }
+        if self.state.is_liveblog_open()
+            && self.last_liveblog_poll.elapsed() >= LIVEBLOG_POLL_INTERVAL
+        {
+            self.poll_liveblog();
+        }
+        if self.state.preview_pending() && self.last_input.elapsed() >= PREVIEW_DELAY {
+            self.state.load_preview();
+            self.render();
+        }
+        if self.state.receive_images() {
+            self.render();
+        }
+        if self.state.watchlist_pending()
+            && self.last_input.elapsed() >= WATCH_CHECK_DELAY
+            && self.state.check_watchlist_body()
+        {
+            self.render();
+        }
+    }
+
+    fn poll_liveblog(&mut self) {
+        self.last_liveblog_poll = Instant::now();
+        match self.state.poll_liveblog() {
+            Ok(new_count) => {
+                self.render();
+                if new_count > 0 {
                     self.renderer
-                        .print_titles(&subset_titles, relative_selected_row);
+                        .write_string(format!("{} new entry/entries", new_count));
                 }
-                Mode::Article => {
-                    let subset_article = self.state.get_subset().to_owned();
-                    self.renderer.print_article(&subset_article);
+            }
+            Err(e) => self
+                .renderer
+                .write_error_string(format!("Updating the liveblog failed: {}", e)),
+        }
+    }
+
+    /// Fetches the article list again and merges it into the current one.
+    fn refresh(&mut self) {
+        self.last_refresh = Instant::now();
+        match self.sources.get_all_items(&self.categories) {
+            Ok(listing) => {
+                let new_articles = self.state.merge_articles(listing.articles, true);
+                for article in &new_articles {
+                    self.run_hook(Hooks::new_article, article);
+                }
+                if !new_articles.is_empty() {
+                    self.renderer
+                        .write_string(format!("{} new article(s)", new_articles.len()));
                 }
             }
+            Err(e) => self
+                .renderer
+                .write_error_string(format!("Refreshing failed: {}", e)),
         }
-        self.renderer.clear_all();
-        self.renderer.show_cursor();
     }
 
     fn enter_article(&mut self) {
-        self.state.enter_article();
+        match self.state.enter_article() {
+            Ok(()) => self.on_open_article(),
+            Err(e) => self.write_open_error(e),
+        }
+    }
+
+    fn write_open_error(&mut self, e: Box<dyn std::error::Error>) {
+        self.renderer
+            .write_error_string(format!("Opening the article failed: {}", e));
+    }
+
+    /// Moves the focus between the list and the article next to it.
+    fn toggle_focus(&mut self) {
+        if !self.state.is_split() {
+            return;
+        }
+        match self.state.mode {
+            Mode::Select => self.enter_article(),
+            Mode::Article => self.state.go_back(),
+        }
+    }
+
+    fn on_open_article(&mut self) {
+        self.last_liveblog_poll = Instant::now();
+        if let Some(article) = self.state.open_article().cloned() {
+            self.run_hook(Hooks::open_article, &article);
+        }
+        self.render();
+    }
+
+    /// Runs a hook, reporting in the status bar when it couldn't be started.
+    fn run_hook(&mut self, hook: fn(&Hooks, &Article) -> std::io::Result<()>, article: &Article) {
+        if let Err(e) = hook(&self.hooks, article) {
+            self.renderer
+                .write_error_string(format!("Running a hook failed: {}", e));
+        }
+    }
 
-        let subset_article = self.state.get_subset().to_owned();
-        self.renderer.print_article(&subset_article);
+    pub fn set_hooks(&mut self, hooks: Hooks) {
+        self.hooks = hooks;
+    }
+
+    /// Selects the clicked title, or opens it when it was already selected.
+    fn click(&mut self, column: usize, row: usize) {
+        // Only the list takes clicks, not the article next to it
+        if self.state.mode != Mode::Select || column >= self.state.list_width() {
+            return;
+        }
+        let Some(row) = row.checked_sub(self.state.tab_bar_rows()) else {
+            return;
+        };
+        if row == self.state.get_relative_row() {
+            self.enter_article();
+        } else {
+            self.state.select_visible_row(row);
+        }
+    }
+
+    /// Opens a video or audio fragment of the article in the media player.
+    fn play_media(&mut self, number: usize) {
+        let Some(media) = self.state.get_media(number) else {
+            self.renderer
+                .write_error_string(format!("No video or audio [{}] here", number));
+            return;
+        };
+        let title = media.to_string();
+        match launch_player(&self.player, &media.url) {
+            Ok(()) => self.renderer.write_string(format!("Playing {}", title)),
+            Err(e) => self
+                .renderer
+                .write_error_string(format!("Starting {} failed: {}", self.player, e)),
+        }
+    }
+
+    /// Writes the open or selected article to `path`, in the format that
+    /// belongs to its extension.
+    fn export(&mut self, path: &str) {
+        if path.is_empty() {
+            self.renderer
+                .write_error_string(String::from("Usage: export <path>"));
+            return;
+        }
+        let path = Path::new(path);
+        let Some(format) = ExportFormat::from_path(path) else {
+            self.renderer.write_error_string(String::from(
+                "Can only export to .md, .html and .epub files",
+            ));
+            return;
+        };
+
+        self.renderer
+            .write_string(String::from("Exporting article..."));
+        let result = self.state.current_document().and_then(|document| {
+            let title = document.article.title.clone();
+            export::write(path, format, &title, &[document])
+        });
+        match result {
+            Ok(()) => self
+                .renderer
+                .write_string(format!("Exported to {}", path.display())),
+            Err(e) => self
+                .renderer
+                .write_error_string(format!("Exporting failed: {}", e)),
+        }
+    }
+
+    fn show_archive(&mut self, query: &str) {
+        let result = Query::parse(query)
+            .map_err(|e| e.into())
+            .and_then(|query| self.state.show_archive(&query));
+        match result {
+            Ok(count) => self
+                .renderer
+                .write_string(format!("{} archived article(s)", count)),
+            Err(e) => self.renderer.write_error_string(e.to_string()),
+        }
+    }
+
+    fn toggle_bookmark(&mut self) {
+        match self.state.toggle_bookmark(self.save_bookmark_bodies) {
+            Ok(true) => {
+                self.renderer.write_string(String::from("Bookmark added"));
+                if let Some(article) = self.state.current_article().cloned() {
+                    self.run_hook(Hooks::bookmark, &article);
+                }
+            }
+            Ok(false) => self.renderer.write_string(String::from("Bookmark removed")),
+            Err(e) => self
+                .renderer
+                .write_error_string(format!("Saving bookmarks failed: {}", e)),
+        }
     }
 
     fn input_mode<F, G>(&mut self, starting_char: char, on_submit: F, on_update: Option<G>)
@@ -129,7 +558,9 @@ impl App {
             |_, _| {}, // TODO: also make this an optional parameter
             Some(|this: &mut Self, input: &str| {
                 let matches_titles = this.state.filter_articles(input);
-                this.renderer.print_titles(&matches_titles, 0);
+                let title_styles = this.state.get_title_styles();
+                this.renderer
+                    .print_titles(&matches_titles, &title_styles, 0, true);
             }),
         );
     }
@@ -145,9 +576,41 @@ impl App {
     }
 
     fn execute_command(&mut self, command: String) {
-        match command.as_str() {
+        let (command, argument) = command.split_once(' ').unwrap_or((&command, ""));
+        match command {
             "random" => self.enter_random_article(),
             "reset" | "noh" => self.state.reset(),
+            "refresh" => self.refresh(),
+            "unread" => self.state.filter_unread(),
+            "hide-read" => self.state.toggle_hide_read(),
+            "mark-all-read" => self.state.mark_all_read(),
+            "bookmark" => self.toggle_bookmark(),
+            "bookmarks" => self.state.toggle_bookmark_view(),
+            "watch" => self.state.toggle_watch_view(),
+            "split" => self.state.toggle_split(),
+            "buffers" | "ls" => self.state.toggle_buffer_view(),
+            "bd" | "bdelete" => self.state.close_tab(),
+            "add-category" => self.add_category(argument.trim()),
+            "export" => self.export(argument.trim()),
+            "archive" => self.show_archive(argument),
+            "sort" => match SortOrder::from_str(argument.trim(), true) {
+                Ok(sort_order) => {
+                    if self.state.set_sort_order(sort_order) {
+                        self.renderer
+                            .write_string(String::from("Stopped grouping by day"));
+                    }
+                }
+                Err(_) => self
+                    .renderer
+                    .write_error_string(String::from("Usage: sort time|title|category|read")),
+            },
+            "group" => self.state.toggle_group_by_day(),
+            "time" => match TimeFormat::from_str(argument.trim(), true) {
+                Ok(time_format) => self.state.set_time_format(time_format),
+                Err(_) => self
+                    .renderer
+                    .write_error_string(String::from("Usage: time original|relative|absolute")),
+            },
             // TODO: switch category
             s => self
                 .renderer
@@ -155,9 +618,63 @@ impl App {
         }
     }
 
+    /// Appends older articles when the bottom of the list is reached.
+    fn load_more(&mut self) {
+        self.renderer
+            .write_string(String::from("Loading older articles..."));
+        match self.state.load_more() {
+            Ok(_) => self.renderer.clear_status_bar(),
+            Err(e) => self
+                .renderer
+                .write_error_string(format!("Loading older articles failed: {}", e)),
+        }
+    }
+
+    /// Adds another category's articles to the list.
+    fn add_category(&mut self, category: &str) {
+        if category.is_empty() {
+            self.renderer
+                .write_error_string(String::from("Usage: add-category <category>"));
+            return;
+        }
+        if self.categories.iter().any(|c| c == category) {
+            return;
+        }
+
+        self.categories.push(category.to_string());
+        match self.sources.get_all_items(&self.categories) {
+            Ok(listing) => {
+                self.state.merge_articles(listing.articles, false);
+                self.state.add_next_pages(listing.next_pages);
+            }
+            Err(e) => {
+                self.categories.pop();
+                self.renderer.write_error_string(e.to_string());
+            }
+        }
+    }
+
     pub fn enter_random_article(&mut self) {
-        self.state.random_article();
-        let subset_article = self.state.get_subset().to_owned();
-        self.renderer.print_article(&subset_article);
+        match self.state.random_article() {
+            Ok(()) => self.on_open_article(),
+            Err(e) => self.write_open_error(e),
+        }
     }
 }
+
+/// Starts `command` with the URL appended, detached from the terminal so the
+/// player's output doesn't end up in the interface.
+fn launch_player(command: &str, url: &str) -> std::io::Result<()> {
+    let mut parts = command.split_whitespace();
+    let program = parts.next().unwrap_or("mpv");
+    let mut child = Command::new(program)
+        .args(parts)
+        .arg(url)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    // Reap the player when it exits
+    thread::spawn(move || child.wait());
+    Ok(())
+}
@@ -0,0 +1,293 @@
+use std::{fs, path::Path};
+
+use chrono::{DateTime, Local, NaiveDate, TimeZone, Utc};
+use rusqlite::{Connection, OptionalExtension, params, params_from_iter, types::Value};
+
+use crate::{
+    scrape::{Article, Block},
+    store,
+};
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS articles (
+    href TEXT PRIMARY KEY,
+    title TEXT NOT NULL,
+    datetime TEXT NOT NULL,
+    published TEXT,
+    published_at INTEGER,
+    category TEXT NOT NULL,
+    source TEXT NOT NULL,
+    first_seen INTEGER NOT NULL,
+    body TEXT
+);
+CREATE VIRTUAL TABLE IF NOT EXISTS articles_fts USING fts5(href UNINDEXED, title, text);
+";
+
+/// Every article that has been listed or opened, stored in `archive.sqlite`
+/// in the data directory so it can still be found after it left the site.
+pub struct Archive {
+    connection: Connection,
+}
+
+/// What to look for in the archive.
+#[derive(Debug, Default, PartialEq)]
+pub struct Query {
+    /// Words that all have to appear in the title or text
+    pub keywords: Vec<String>,
+    /// First day to include
+    pub since: Option<NaiveDate>,
+    /// Last day to include
+    pub until: Option<NaiveDate>,
+}
+
+impl Query {
+    /// Parses words with optional `since:YYYY-MM-DD` and `until:YYYY-MM-DD`
+    /// filters, as typed after `:archive`.
+    pub fn parse(input: &str) -> Result<Self, String> {
+        let mut query = Query::default();
+        for word in input.split_whitespace() {
+            let date = |value: &str| {
+                NaiveDate::parse_from_str(value, "%Y-%m-%d")
+                    .map_err(|_| format!("Invalid date `{}`, use YYYY-MM-DD", value))
+            };
+            if let Some(value) = word.strip_prefix("since:") {
+                query.since = Some(date(value)?);
+            } else if let Some(value) = word.strip_prefix("until:") {
+                query.until = Some(date(value)?);
+            } else {
+                query.keywords.push(word.to_string());
+            }
+        }
+        Ok(query)
+    }
+
+    /// The keywords as an FTS5 query, each quoted so punctuation in them
+    /// isn't read as query syntax.
+    fn match_expression(&self) -> String {
+        self.keywords
+            .iter()
+            .map(|keyword| format!("\"{}\"", keyword.replace('"', "\"\"")))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+impl Archive {
+    pub fn open() -> Result<Self, Box<dyn std::error::Error>> {
+        let dir = store::data_dir().ok_or("No data directory to keep the archive in")?;
+        fs::create_dir_all(&dir)?;
+        Self::open_at(&dir.join("archive.sqlite"))
+    }
+
+    fn open_at(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        Self::with_connection(Connection::open(path)?)
+    }
+
+    fn with_connection(connection: Connection) -> Result<Self, Box<dyn std::error::Error>> {
+        connection.execute_batch(SCHEMA)?;
+        Ok(Archive { connection })
+    }
+
+    /// Adds the articles of a listing, updating the ones that are known.
+    pub fn add_articles(&self, articles: &[Article]) -> Result<(), Box<dyn std::error::Error>> {
+        let transaction = self.connection.unchecked_transaction()?;
+        let now = Utc::now().timestamp();
+        for article in articles {
+            transaction.execute(
+                "INSERT INTO articles
+                    (href, title, datetime, published, published_at, category, source, first_seen)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+                ON CONFLICT (href) DO UPDATE SET
+                    title = excluded.title,
+                    datetime = excluded.datetime,
+                    published = COALESCE(excluded.published, published),
+                    published_at = COALESCE(excluded.published_at, published_at),
+                    source = excluded.source",
+                params![
+                    article.href,
+                    article.title,
+                    article.datetime,
+                    article.published.map(|p| p.to_rfc3339()),
+                    article.published.map(|p| p.timestamp()),
+                    article.category,
+                    article.source,
+                    now,
+                ],
+            )?;
+            index(&transaction, &article.href)?;
+        }
+        transaction.commit()?;
+        Ok(())
+    }
+
+    /// Stores the body of an article, adding the article when it's new.
+    pub fn add_body(
+        &self,
+        article: &Article,
+        blocks: &[Block],
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        self.add_articles(std::slice::from_ref(article))?;
+        self.connection.execute(
+            "UPDATE articles SET body = ?1 WHERE href = ?2",
+            params![serde_json::to_string(blocks)?, article.href],
+        )?;
+        index(&self.connection, &article.href)?;
+        Ok(())
+    }
+
+    pub fn get_body(&self, href: &str) -> Result<Option<Vec<Block>>, Box<dyn std::error::Error>> {
+        let body: Option<Option<String>> = self
+            .connection
+            .query_row(
+                "SELECT body FROM articles WHERE href = ?1",
+                params![href],
+                |row| row.get(0),
+            )
+            .optional()?;
+        match body.flatten() {
+            Some(body) => Ok(Some(serde_json::from_str(&body)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Articles matching the query, newest first. Articles without a
+    /// publication time count as published when they were first seen.
+    pub fn search(
+        &self,
+        query: &Query,
+        limit: usize,
+    ) -> Result<Vec<Article>, Box<dyn std::error::Error>> {
+        let mut sql = String::from(
+            "SELECT a.title, a.href, a.datetime, a.published, a.category, a.source
+            FROM articles a",
+        );
+        let mut conditions = Vec::new();
+        let mut values: Vec<Value> = Vec::new();
+
+        if !query.keywords.is_empty() {
+            sql.push_str(" JOIN articles_fts f ON f.href = a.href");
+            conditions.push("articles_fts MATCH ?");
+            values.push(Value::Text(query.match_expression()));
+        }
+        if let Some(since) = query.since {
+            conditions.push("COALESCE(a.published_at, a.first_seen) >= ?");
+            values.push(Value::Integer(start_of_day(since)));
+        }
+        if let Some(until) = query.until {
+            conditions.push("COALESCE(a.published_at, a.first_seen) < ?");
+            values.push(Value::Integer(start_of_day(
+                until.succ_opt().unwrap_or(until),
+            )));
+        }
+        if !conditions.is_empty() {
+            sql.push_str(" WHERE ");
+            sql.push_str(&conditions.join(" AND "));
+        }
+        sql.push_str(" ORDER BY COALESCE(a.published_at, a.first_seen) DESC LIMIT ?");
+        values.push(Value::Integer(limit as i64));
+
+        let mut statement = self.connection.prepare(&sql)?;
+        let articles = statement
+            .query_map(params_from_iter(values), |row| {
+                let published: Option<String> = row.get(3)?;
+                Ok(Article {
+                    title: row.get(0)?,
+                    href: row.get(1)?,
+                    datetime: row.get(2)?,
+                    published: published.and_then(|p| DateTime::parse_from_rfc3339(&p).ok()),
+                    category: row.get(4)?,
+                    source: row.get(5)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(articles)
+    }
+}
+
+/// Replaces the search index entry of an article with its current title and text.
+fn index(connection: &Connection, href: &str) -> rusqlite::Result<()> {
+    let (title, body): (String, Option<String>) = connection.query_row(
+        "SELECT title, body FROM articles WHERE href = ?1",
+        params![href],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    )?;
+    let text = body
+        .and_then(|body| serde_json::from_str::<Vec<Block>>(&body).ok())
+        .map(|blocks| plain_text(&blocks))
+        .unwrap_or_default();
+
+    connection.execute("DELETE FROM articles_fts WHERE href = ?1", params![href])?;
+    connection.execute(
+        "INSERT INTO articles_fts (href, title, text) VALUES (?1, ?2, ?3)",
+        params![href, title, text],
+    )?;
+    Ok(())
+}
+
+fn plain_text(blocks: &[Block]) -> String {
+    blocks
+        .iter()
+        .map(|block| match block {
+            Block::Text(text) | Block::Linked { text, .. } => text.as_str(),
+            Block::Heading { heading } => heading.as_str(),
+            Block::Image(image) => image.caption.as_str(),
+            Block::Media(media) => media.title.as_str(),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Local midnight at the start of `day`, as a Unix timestamp.
+fn start_of_day(day: NaiveDate) -> i64 {
+    let midnight = day.and_hms_opt(0, 0, 0).unwrap_or_default();
+    Local
+        .from_local_datetime(&midnight)
+        .earliest()
+        .map(|time| time.timestamp())
+        .unwrap_or_else(|| midnight.and_utc().timestamp())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util;
+
+    #[test]
+    fn test_search() {
+        let archive = Archive::with_connection(Connection::open_in_memory().unwrap()).unwrap();
+        let article = |href: &str, title: &str, time: &str| Article {
+            title: title.to_string(),
+            href: href.to_string(),
+            datetime: String::new(),
+            published: util::parse_timestamp(time),
+            category: String::from("binnenland"),
+            source: String::from("nos"),
+        };
+        let storm = article("storm", "Code oranje", "2025-07-10T10:00:00+0200");
+        archive
+            .add_articles(&[
+                storm.clone(),
+                article("kabinet", "Kabinet valt", "2025-06-01T10:00:00+0200"),
+            ])
+            .unwrap();
+        archive
+            .add_body(
+                &storm,
+                &[Block::Text(String::from("Het gaat hard waaien."))],
+            )
+            .unwrap();
+
+        let hrefs = |query: &str| -> Vec<String> {
+            let query = Query::parse(query).unwrap();
+            let articles = archive.search(&query, 10).unwrap();
+            articles.into_iter().map(|a| a.href).collect()
+        };
+        assert_eq!(hrefs("waaien"), ["storm"]);
+        assert_eq!(hrefs("kabinet"), ["kabinet"]);
+        assert_eq!(hrefs(""), ["storm", "kabinet"]);
+        assert_eq!(hrefs("until:2025-06-30"), ["kabinet"]);
+        assert_eq!(hrefs("since:2025-07-10 until:2025-07-10"), ["storm"]);
+        assert!(archive.get_body("storm").unwrap().is_some());
+        assert!(Query::parse("since:gisteren").is_err());
+    }
+}
@@ -1,13 +1,144 @@
-use clap::Parser;
+use std::path::PathBuf;
+
+use chrono::NaiveDate;
+use clap::{Parser, Subcommand};
+
+use crate::{
+    export::ExportFormat, graphics::ImageProtocol, nos::Backend, state::Layout, util::TimeFormat,
+};
 
 #[derive(Parser, Debug)]
 #[command(version, about, long_about = None)]
 pub struct Args {
-    /// Category to show articles for
-    #[arg(short, long, default_value_t = String::from("laatste"))]
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// Category to show articles for, or a comma-separated list of categories
+    #[arg(short, long, global = true, default_value_t = String::from("laatste"))]
     pub category: String,
 
+    /// Where to read the article lists from, the other one is used when this yields no articles
+    #[arg(long, value_enum, default_value_t = Backend::Html)]
+    pub source: Backend,
+
+    /// Site profile with the URLs and selectors to scrape with [default: ~/.config/nos-cli/profile.toml]
+    #[arg(long, value_name = "PATH")]
+    pub profile: Option<PathBuf>,
+
+    /// Keywords and patterns to highlight articles for [default: ~/.config/nos-cli/watchlist.toml]
+    #[arg(long, global = true, value_name = "PATH")]
+    pub watchlist: Option<PathBuf>,
+
+    /// Commands to run when articles are new, opened or bookmarked [default: ~/.config/nos-cli/hooks.toml]
+    #[arg(long, global = true, value_name = "PATH")]
+    pub hooks: Option<PathBuf>,
+
+    /// Print the built-in site profile and exit
+    #[arg(long)]
+    pub print_profile: bool,
+
+    /// Print the categories of every source and exit
+    #[arg(long)]
+    pub list_categories: bool,
+
     /// Open a random article
     #[arg(long)]
     pub random: bool,
+
+    /// Refresh the article list every N seconds
+    #[arg(long, value_name = "SECONDS", value_parser = clap::value_parser!(u64).range(1..))]
+    pub refresh_interval: Option<u64>,
+
+    /// How to show the publication time of articles
+    #[arg(long, value_enum, default_value_t = TimeFormat::Original)]
+    pub time_format: TimeFormat,
+
+    /// How to draw the images in articles
+    #[arg(long, value_enum, default_value_t = ImageProtocol::Auto)]
+    pub images: ImageProtocol,
+
+    /// Whether to show the selected article next to the list
+    #[arg(long, value_enum, default_value_t = Layout::Single)]
+    pub layout: Layout,
+
+    /// Part of the width the list takes up when split
+    #[arg(long, value_name = "RATIO", default_value_t = 0.4, value_parser = parse_ratio)]
+    pub split_ratio: f64,
+
+    /// Command to play videos and audio with, the URL is added to the end
+    #[arg(long, value_name = "COMMAND", default_value_t = String::from("mpv"))]
+    pub player: String,
+
+    /// Export the newest article of the category, or a random one with --random, and exit
+    #[arg(long, value_name = "PATH")]
+    pub export: Option<PathBuf>,
+
+    /// Format to export to [default: based on the extension of the path]
+    #[arg(long, value_enum)]
+    pub export_format: Option<ExportFormat>,
+
+    /// Store the article text with new bookmarks, so they can be read offline
+    #[arg(long)]
+    pub save_bookmark_bodies: bool,
+
+    /// Keep every listed and opened article in a local archive that can be searched later
+    #[arg(long)]
+    pub archive: bool,
+}
+
+fn parse_ratio(ratio: &str) -> Result<f64, String> {
+    match ratio.parse::<f64>() {
+        Ok(ratio) if (0.1..=0.9).contains(&ratio) => Ok(ratio),
+        _ => Err(String::from("expected a number between 0.1 and 0.9")),
+    }
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Check whether the scrapers still understand the pages of every category
+    Doctor,
+    /// Keep polling the categories and send a desktop notification for new articles
+    Watch {
+        /// Seconds between polls
+        #[arg(long, value_name = "SECONDS", default_value_t = 300, value_parser = clap::value_parser!(u64).range(10..))]
+        interval: u64,
+
+        /// Run this shell command instead of notify-send, with the article in NOS_TITLE, NOS_URL, NOS_CATEGORY, NOS_TIME and NOS_WATCHLIST
+        #[arg(long, value_name = "COMMAND")]
+        notify_command: Option<String>,
+
+        /// Only notify about articles that match the watchlist
+        #[arg(long)]
+        watchlist_only: bool,
+    },
+    /// Search the archive kept with --archive
+    Search {
+        /// Words that all have to appear in the title or text
+        keywords: Vec<String>,
+
+        /// Only articles published on or after this day
+        #[arg(long, value_name = "YYYY-MM-DD")]
+        since: Option<NaiveDate>,
+
+        /// Only articles published on or before this day
+        #[arg(long, value_name = "YYYY-MM-DD")]
+        until: Option<NaiveDate>,
+
+        /// Maximum number of articles to show
+        #[arg(short = 'n', long, default_value_t = 50)]
+        limit: usize,
+    },
+    /// Write the newest articles of the categories to one document with a table of contents
+    Digest {
+        /// File to write to, the format is based on its extension
+        path: PathBuf,
+
+        /// Number of articles per category
+        #[arg(short = 'n', long, default_value_t = 5, value_parser = clap::value_parser!(u64).range(1..))]
+        count: u64,
+
+        /// Format to write [default: based on the extension of the path]
+        #[arg(long, value_enum)]
+        format: Option<ExportFormat>,
+    },
 }
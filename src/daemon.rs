@@ -0,0 +1,155 @@
+use std::{collections::HashSet, process::Command, thread, time::Duration};
+
+use crate::{
+    hooks::{self, Hooks},
+    scrape::Article,
+    source::Sources,
+    store::Notified,
+    watchlist::Watchlist,
+};
+
+pub struct Options {
+    pub interval: Duration,
+    /// Run through `sh -c` instead of `notify-send`, with the article in the environment
+    pub command: Option<String>,
+    /// Only notify about articles that match the watchlist
+    pub watchlist_only: bool,
+}
+
+/// Polls the categories until interrupted and sends a notification for
+/// every article that wasn't notified about before. On the very first run
+/// the articles that are already listed are only recorded.
+pub fn run(
+    sources: &Sources,
+    categories: &[String],
+    watchlist: &Watchlist,
+    hooks: &Hooks,
+    options: &Options,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut notified = Notified::load();
+    let mut quiet = notified.is_new();
+    if quiet {
+        println!("First run, recording the current articles without notifying");
+    }
+    // Articles that failed to notify are retried, but their hook only runs once
+    let mut hooked = HashSet::new();
+
+    loop {
+        match sources.get_all_items(categories) {
+            Ok(listing) => {
+                let mut done = Vec::new();
+                for article in new_articles(&listing.articles, &notified) {
+                    if quiet {
+                        done.push(article.href.as_str());
+                        continue;
+                    }
+                    if hooked.insert(article.href.clone())
+                        && let Err(e) = hooks.new_article(article)
+                    {
+                        eprintln!("Running on_new_article failed: {}", e);
+                    }
+                    let Some(watched) = matches_watchlist(sources, watchlist, article) else {
+                        eprintln!("Fetching {} failed, trying again later", article.href);
+                        continue;
+                    };
+                    if options.watchlist_only && !watched {
+                        done.push(article.href.as_str());
+                        continue;
+                    }
+                    println!("{}{}", if watched { "[watch] " } else { "" }, article.title);
+                    match notify(article, watched, options.command.as_deref()) {
+                        Ok(()) => done.push(article.href.as_str()),
+                        Err(e) => eprintln!("Notifying failed, trying again later: {}", e),
+                    }
+                }
+                notified.add(done)?;
+                quiet = false;
+            }
+            Err(e) => eprintln!("Fetching the articles failed: {}", e),
+        }
+        thread::sleep(options.interval);
+    }
+}
+
+/// The listed articles that haven't been notified about, oldest first so
+/// notifications arrive in the order of publication.
+fn new_articles<'a>(articles: &'a [Article], notified: &Notified) -> Vec<&'a Article> {
+    let mut seen = HashSet::new();
+    articles
+        .iter()
+        .rev()
+        .filter(|article| !notified.contains(&article.href) && seen.insert(&article.href))
+        .collect()
+}
+
+/// Whether the article matches the watchlist, `None` when that depends on
+/// its body and fetching it failed.
+fn matches_watchlist(sources: &Sources, watchlist: &Watchlist, article: &Article) -> Option<bool> {
+    if watchlist.matches(&article.title) {
+        return Some(true);
+    }
+    if watchlist.is_empty() || !watchlist.bodies {
+        return Some(false);
+    }
+    sources
+        .get_article(article)
+        .ok()
+        .map(|blocks| watchlist.matches_body(&blocks))
+}
+
+fn notify(article: &Article, watched: bool, command: Option<&str>) -> std::io::Result<()> {
+    let status = match command {
+        Some(command) => Command::new("sh")
+            .arg("-c")
+            .arg(command)
+            .envs(hooks::environment("notify", article))
+            .env("NOS_WATCHLIST", if watched { "1" } else { "0" })
+            .status()?,
+        None => {
+            let summary = if watched {
+                format!("Watchlist: {}", article.title)
+            } else {
+                article.title.clone()
+            };
+            Command::new("notify-send")
+                .arg("--app-name=nos-cli")
+                .arg(format!(
+                    "--urgency={}",
+                    if watched { "critical" } else { "normal" }
+                ))
+                .arg(summary)
+                .arg(format!("{} · {}", article.category, article.href))
+                .status()?
+        }
+    };
+    if !status.success() {
+        return Err(std::io::Error::other(format!("exited with {}", status)));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_new_articles() {
+        let article = |href: &str| Article {
+            title: href.to_uppercase(),
+            href: href.to_string(),
+            datetime: String::new(),
+            published: None,
+            category: String::from("binnenland"),
+            source: String::from("nos"),
+        };
+        let mut notified = Notified::load_at(None);
+        notified.add(["b"]).unwrap();
+
+        let articles = [article("c"), article("b"), article("a"), article("c")];
+        let hrefs: Vec<&str> = new_articles(&articles, &notified)
+            .iter()
+            .map(|article| article.href.as_str())
+            .collect();
+        assert_eq!(hrefs, ["c", "a"]);
+    }
+}
@@ -0,0 +1,97 @@
+use std::{collections::HashSet, path::Path};
+
+use chrono::Local;
+
+use crate::{
+    export::{self, ExportFormat},
+    scrape::Article,
+    source::Sources,
+};
+
+/// Fetches the newest `count` articles of every category and writes them to
+/// `path` as one document with a table of contents. Articles that can't be
+/// fetched are left out.
+pub fn run(
+    sources: &Sources,
+    categories: &[String],
+    count: usize,
+    path: &Path,
+    format: ExportFormat,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut listings = Vec::new();
+    for category in categories {
+        let page = sources.get_listing(category)?;
+        listings.push(page.articles);
+    }
+    let articles = pick_articles(listings, count);
+    if articles.is_empty() {
+        return Err("No articles found".into());
+    }
+
+    let mut documents = Vec::new();
+    for (i, article) in articles.into_iter().enumerate() {
+        println!("[{}] {}", i + 1, article.title);
+        match export::fetch_body(sources, article) {
+            Ok(document) => documents.push(document),
+            Err(e) => eprintln!("  Skipped: {}", e),
+        }
+    }
+
+    let title = format!("NOS-digest {}", Local::now().format("%Y-%m-%d"));
+    export::write(path, format, &title, &documents)?;
+    println!("Wrote {} articles to {}", documents.len(), path.display());
+    Ok(())
+}
+
+/// The newest `count` articles of every listing, in the order of the
+/// listings. Articles already picked for an earlier category are skipped.
+fn pick_articles(listings: Vec<Vec<Article>>, count: usize) -> Vec<Article> {
+    let mut seen_hrefs = HashSet::new();
+    let mut picked = Vec::new();
+
+    for mut articles in listings {
+        articles.sort_by_key(|a| std::cmp::Reverse(a.published));
+        picked.extend(
+            articles
+                .into_iter()
+                .filter(|a| seen_hrefs.insert(a.href.clone()))
+                .take(count),
+        );
+    }
+    picked
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util;
+
+    fn article(href: &str, time: &str) -> Article {
+        Article {
+            title: href.to_string(),
+            href: href.to_string(),
+            datetime: String::new(),
+            published: util::parse_timestamp(time),
+            category: String::new(),
+            source: String::from("nos"),
+        }
+    }
+
+    #[test]
+    fn test_pick_articles() {
+        let listings = vec![
+            vec![
+                article("a", "2025-07-10T08:00:00+0200"),
+                article("b", "2025-07-10T10:00:00+0200"),
+                article("c", "2025-07-10T09:00:00+0200"),
+            ],
+            vec![
+                article("b", "2025-07-10T10:00:00+0200"),
+                article("d", "2025-07-10T07:00:00+0200"),
+            ],
+        ];
+        let picked = pick_articles(listings, 2);
+        let hrefs: Vec<&str> = picked.iter().map(|a| a.href.as_str()).collect();
+        assert_eq!(hrefs, ["b", "c", "d"]);
+    }
+}
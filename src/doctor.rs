@@ -0,0 +1,179 @@
+use scraper::{Html, Selector};
+
+use crate::{
+    rss,
+    scrape::{Article, Block},
+    source::{Source, Sources},
+};
+
+/// Fetches every category, its RSS feed when it has one, one article per
+/// source and one liveblog, and reports how well the scrapers still match
+/// the pages. Returns whether everything worked.
+pub fn run(sources: &Sources) -> bool {
+    let mut healthy = true;
+    let mut listed = Vec::new();
+
+    for (name, categories) in sources.categories() {
+        let mut sample = None;
+
+        for category in categories {
+            let source = sources.for_category(category);
+            println!("{}/{}", name, category);
+            match check_category(source, category) {
+                Ok((ok, articles)) => {
+                    healthy &= ok;
+                    if sample.is_none() {
+                        sample = articles
+                            .iter()
+                            .find(|a| !a.href.is_empty() && !sources.is_liveblog(a))
+                            .cloned();
+                    }
+                    listed.extend(articles);
+                }
+                Err(e) => healthy &= failed(e),
+            }
+
+            if let Some(url) = source.feed_url(category) {
+                println!("{}/{} (rss)", name, category);
+                let ok = check_feed(&url, category).unwrap_or_else(failed);
+                // Only a fallback while the pages are scraped
+                if !ok && !source.reads_feeds() {
+                    println!("  (not in use, only a warning)");
+                } else {
+                    healthy &= ok;
+                }
+            }
+        }
+
+        let Some(article) = sample else {
+            println!("{}: no article to check", name);
+            healthy = false;
+            continue;
+        };
+        println!("{}: {}", name, article.href);
+        match check_article(sources.for_article(&article), &article.href) {
+            Ok(ok) => healthy &= ok,
+            Err(e) => healthy &= failed(e),
+        }
+    }
+
+    // Liveblogs only run while something is happening, so not finding one is fine
+    match listed.iter().find(|a| sources.is_liveblog(a)) {
+        Some(article) => {
+            println!("liveblog: {}", article.href);
+            match check_liveblog(sources, &article.href) {
+                Ok(ok) => healthy &= ok,
+                Err(e) => healthy &= failed(e),
+            }
+        }
+        None => println!("liveblog: none listed, not checked"),
+    }
+
+    println!();
+    println!("{}", if healthy { "All OK" } else { "Broken" });
+    healthy
+}
+
+/// Reports an error that stopped a check. Always returns false, for `healthy &=`.
+fn failed(e: Box<dyn std::error::Error>) -> bool {
+    println!("  FAILED: {}", e);
+    false
+}
+
+fn check_category(
+    source: &dyn Source,
+    category: &str,
+) -> Result<(bool, Vec<Article>), Box<dyn std::error::Error>> {
+    let url = source.category_url(category);
+    let document = Html::parse_document(&fetch(&url)?);
+    print_selector_counts(&document, &source.listing_selectors())?;
+
+    let articles = source.parse_listing(&document, &url, category)?.articles;
+    let ok = check_articles(&articles);
+    Ok((ok, articles))
+}
+
+fn check_feed(url: &str, category: &str) -> Result<bool, Box<dyn std::error::Error>> {
+    let body = reqwest::blocking::get(url)?.error_for_status()?.bytes()?;
+    let articles = rss::parse_items(&body, category)?.articles;
+    Ok(check_articles(&articles))
+}
+
+/// Prints how many articles in a listing miss something. Returns whether
+/// the listing is usable.
+fn check_articles(articles: &[Article]) -> bool {
+    let count_empty = |field: fn(&Article) -> bool| articles.iter().filter(|a| field(a)).count();
+    let without_title = count_empty(|a| a.title.trim().is_empty());
+    let without_link = count_empty(|a| a.href.is_empty());
+    let without_time = count_empty(|a| a.datetime.is_empty());
+    let without_timestamp = count_empty(|a| a.published.is_none());
+
+    println!("  {} article(s)", articles.len());
+    println!("  {} without title", without_title);
+    println!("  {} without link", without_link);
+    println!("  {} without time", without_time);
+    println!("  {} without parsable timestamp", without_timestamp);
+
+    // A missing time is tolerable, articles without title or link are not
+    let ok = !articles.is_empty() && without_title == 0 && without_link == 0;
+    println!("  {}", if ok { "OK" } else { "BROKEN" });
+    ok
+}
+
+fn check_article(source: &dyn Source, url: &str) -> Result<bool, Box<dyn std::error::Error>> {
+    let document = Html::parse_document(&fetch(url)?);
+    print_selector_counts(&document, &source.article_selectors())?;
+
+    let blocks = source.parse_article(&document, url)?;
+    let text: Vec<&String> = blocks
+        .iter()
+        .filter_map(|block| match block {
+            Block::Text(text) | Block::Linked { text, .. } => Some(text),
+            _ => None,
+        })
+        .collect();
+    let empty = text.iter().filter(|line| line.trim().is_empty()).count();
+    println!("  {} paragraph(s)", text.len());
+    println!("  {} empty", empty);
+    let count = |matches: fn(&Block) -> bool| blocks.iter().filter(|b| matches(b)).count();
+    println!("  {} image(s)", count(|b| matches!(b, Block::Image(_))));
+    println!("  {} video/audio", count(|b| matches!(b, Block::Media(_))));
+
+    let ok = text.len() > empty;
+    println!("  {}", if ok { "OK" } else { "BROKEN" });
+    Ok(ok)
+}
+
+fn check_liveblog(sources: &Sources, url: &str) -> Result<bool, Box<dyn std::error::Error>> {
+    let document = Html::parse_document(&fetch(url)?);
+    print_selector_counts(&document, &sources.liveblog_selectors())?;
+
+    let entries = sources.parse_liveblog(&document)?;
+    let without_title = entries.iter().filter(|e| e.title.is_empty()).count();
+    let without_text = entries.iter().filter(|e| e.paragraphs.is_empty()).count();
+    let without_timestamp = entries.iter().filter(|e| e.published.is_none()).count();
+    println!("  {} entries", entries.len());
+    println!("  {} without title", without_title);
+    println!("  {} without text", without_text);
+    println!("  {} without parsable timestamp", without_timestamp);
+
+    let ok = entries.len() > without_text;
+    println!("  {}", if ok { "OK" } else { "BROKEN" });
+    Ok(ok)
+}
+
+fn fetch(url: &str) -> Result<String, Box<dyn std::error::Error>> {
+    Ok(reqwest::blocking::get(url)?.error_for_status()?.text()?)
+}
+
+fn print_selector_counts(
+    document: &Html,
+    selectors: &[String],
+) -> Result<(), Box<dyn std::error::Error>> {
+    for selector in selectors {
+        let parsed = Selector::parse(selector).map_err(|e| e.to_string())?;
+        let count = document.select(&parsed).count();
+        println!("  {:>4}  {}", count, selector);
+    }
+    Ok(())
+}
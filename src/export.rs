@@ -0,0 +1,485 @@
+use std::{
+    fs::{self, File},
+    io::Write,
+    path::Path,
+};
+
+use chrono::{Local, Utc};
+use rand::seq::IndexedRandom;
+use zip::{CompressionMethod, ZipWriter, write::SimpleFileOptions};
+
+use crate::{
+    liveblog,
+    scrape::{Article, Block, Link},
+    source::Sources,
+    util,
+};
+
+/// File formats articles can be exported to.
+#[derive(Clone, Copy, Debug, PartialEq, clap::ValueEnum)]
+pub enum ExportFormat {
+    Markdown,
+    /// A standalone HTML page
+    Html,
+    Epub,
+}
+
+impl ExportFormat {
+    /// Picks the format that belongs to the extension of `path`.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_lowercase();
+        match extension.as_str() {
+            "md" | "markdown" => Some(ExportFormat::Markdown),
+            "html" | "htm" => Some(ExportFormat::Html),
+            "epub" => Some(ExportFormat::Epub),
+            _ => None,
+        }
+    }
+}
+
+/// An article together with its body.
+#[derive(Clone)]
+pub struct Document {
+    pub article: Article,
+    pub blocks: Vec<Block>,
+}
+
+/// Writes the articles to `path` as one document. A table of contents is
+/// added when there's more than one article.
+pub fn write(
+    path: &Path,
+    format: ExportFormat,
+    title: &str,
+    documents: &[Document],
+) -> Result<(), Box<dyn std::error::Error>> {
+    match format {
+        ExportFormat::Markdown => fs::write(path, to_markdown(title, documents))?,
+        ExportFormat::Html => fs::write(path, to_html(title, documents))?,
+        ExportFormat::Epub => write_epub(path, title, documents)?,
+    }
+    Ok(())
+}
+
+/// Fetches the newest article of `categories`, or a random one, with its body.
+pub fn fetch_document(
+    sources: &Sources,
+    categories: &[String],
+    random: bool,
+) -> Result<Document, Box<dyn std::error::Error>> {
+    let listing = sources.get_all_items(categories)?;
+    let article = if random {
+        listing.articles.choose(&mut rand::rng())
+    } else {
+        listing
+            .articles
+            .iter()
+            // The first of any ties, listings put the newest first
+            .min_by_key(|article| std::cmp::Reverse(article.published))
+    }
+    .ok_or("No articles found")?
+    .clone();
+    fetch_body(sources, article)
+}
+
+/// Fetches the body of an article, liveblogs as their list of updates.
+pub fn fetch_body(
+    sources: &Sources,
+    article: Article,
+) -> Result<Document, Box<dyn std::error::Error>> {
+    let blocks = if sources.is_liveblog(&article) {
+        liveblog::entries_to_blocks(&sources.get_liveblog(&article.href)?)
+    } else {
+        sources.get_article(&article)?
+    };
+    Ok(Document { article, blocks })
+}
+
+/// Category, publication time and link of an article.
+fn metadata(article: &Article) -> Vec<String> {
+    let mut metadata = Vec::new();
+    if !article.category.is_empty() {
+        metadata.push(article.category.clone());
+    }
+    match article.published {
+        Some(published) => metadata.push(util::format_absolute(published, Local::now())),
+        None if !article.datetime.is_empty() => metadata.push(article.datetime.clone()),
+        None => (),
+    }
+    metadata
+}
+
+fn to_markdown(title: &str, documents: &[Document]) -> String {
+    let mut markdown = String::new();
+    // A single article is its own title
+    let nested = documents.len() > 1;
+    let heading = if nested { "##" } else { "#" };
+
+    if nested {
+        markdown.push_str(&format!("# {}\n\n", escape_markdown(title)));
+        for (i, document) in documents.iter().enumerate() {
+            markdown.push_str(&format!(
+                "{}. [{}](#artikel-{})\n",
+                i + 1,
+                escape_markdown(&document.article.title),
+                i + 1
+            ));
+        }
+        markdown.push('\n');
+    }
+
+    for (i, document) in documents.iter().enumerate() {
+        let article = &document.article;
+        if nested {
+            markdown.push_str(&format!("<a id=\"artikel-{}\"></a>\n\n", i + 1));
+        }
+        markdown.push_str(&format!(
+            "{} {}\n\n",
+            heading,
+            escape_markdown(&article.title)
+        ));
+        let mut metadata: Vec<String> = metadata(article)
+            .iter()
+            .map(|m| escape_markdown(m))
+            .collect();
+        metadata.push(format!("[Bron]({})", markdown_url(&article.href)));
+        markdown.push_str(&format!("*{}*\n\n", metadata.join(" · ")));
+
+        for block in &document.blocks {
+            match block {
+                Block::Text(text) => markdown.push_str(&format!("{}\n\n", escape_markdown(text))),
+                Block::Linked { text, links } => {
+                    for (text, href) in link_segments(text, links) {
+                        match href {
+                            Some(href) => markdown.push_str(&format!(
+                                "[{}]({})",
+                                escape_markdown(text),
+                                markdown_url(href)
+                            )),
+                            None => markdown.push_str(&escape_markdown(text)),
+                        }
+                    }
+                    markdown.push_str("\n\n");
+                }
+                Block::Heading { heading: text } => {
+                    markdown.push_str(&format!("{}# {}\n\n", heading, escape_markdown(text)))
+                }
+                Block::Image(image) => {
+                    let caption = escape_markdown(&image.caption);
+                    markdown.push_str(&format!("![{}]({})\n\n", caption, markdown_url(&image.url)));
+                    if !image.caption.is_empty() {
+                        markdown.push_str(&format!("*{}*\n\n", caption));
+                    }
+                }
+                Block::Media(media) => markdown.push_str(&format!(
+                    "[▶ {}]({})\n\n",
+                    escape_markdown(&media.to_string()),
+                    markdown_url(&media.url)
+                )),
+            }
+        }
+    }
+    markdown
+}
+
+/// Escapes the characters Markdown would read as formatting.
+fn escape_markdown(text: &str) -> String {
+    let mut escaped = String::new();
+    for c in text.chars() {
+        if "\\`*_[]<>#|~".contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    // Text that starts like a list item would become one
+    let digits = escaped.chars().take_while(char::is_ascii_digit).count();
+    let list_marker = match escaped[digits..].chars().next() {
+        Some('-' | '+') => digits == 0,
+        Some('.' | ')') => digits > 0,
+        _ => false,
+    };
+    if list_marker && escaped[digits + 1..].starts_with(' ') {
+        escaped.insert(digits, '\\');
+    }
+    escaped
+}
+
+/// A URL that can go between the parentheses of a Markdown link.
+fn markdown_url(url: &str) -> String {
+    url.replace(' ', "%20")
+        .replace('(', "%28")
+        .replace(')', "%29")
+}
+
+/// Splits a paragraph into the text between its links and the links, with
+/// their href. Links whose text can't be found in the paragraph are left out.
+fn link_segments<'a>(text: &'a str, links: &'a [Link]) -> Vec<(&'a str, Option<&'a str>)> {
+    let mut segments = Vec::new();
+    let mut rest = text;
+    for link in links {
+        let Some(start) = rest.find(&link.text) else {
+            continue;
+        };
+        let end = start + link.text.len();
+        segments.push((&rest[..start], None));
+        segments.push((&rest[start..end], Some(link.href.as_str())));
+        rest = &rest[end..];
+    }
+    segments.push((rest, None));
+    segments
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// The body of an article as HTML, also used for EPUB chapters.
+fn article_html(document: &Document, heading: u8, id: Option<String>) -> String {
+    let article = &document.article;
+    let id = id.map(|id| format!(" id=\"{}\"", id)).unwrap_or_default();
+    let mut metadata: Vec<String> = metadata(article).iter().map(|m| escape(m)).collect();
+    metadata.push(format!("<a href=\"{}\">Bron</a>", escape(&article.href)));
+
+    let mut html = format!(
+        "<article{}>\n<h{}>{}</h{}>\n<p class=\"meta\">{}</p>\n",
+        id,
+        heading,
+        escape(&article.title),
+        heading,
+        metadata.join(" · ")
+    );
+    for block in &document.blocks {
+        match block {
+            Block::Text(text) => html.push_str(&format!("<p>{}</p>\n", escape(text))),
+            Block::Linked { text, links } => {
+                html.push_str("<p>");
+                for (text, href) in link_segments(text, links) {
+                    match href {
+                        Some(href) => html.push_str(&format!(
+                            "<a href=\"{}\">{}</a>",
+                            escape(href),
+                            escape(text)
+                        )),
+                        None => html.push_str(&escape(text)),
+                    }
+                }
+                html.push_str("</p>\n");
+            }
+            Block::Heading { heading: text } => html.push_str(&format!(
+                "<h{}>{}</h{}>\n",
+                heading + 1,
+                escape(text),
+                heading + 1
+            )),
+            Block::Image(image) => html.push_str(&format!(
+                "<figure><img src=\"{}\" alt=\"{}\"/><figcaption>{}</figcaption></figure>\n",
+                escape(&image.url),
+                escape(&image.caption),
+                escape(&image.caption)
+            )),
+            Block::Media(media) => html.push_str(&format!(
+                "<p><a href=\"{}\">▶ {}</a></p>\n",
+                escape(&media.url),
+                escape(&media.to_string())
+            )),
+        }
+    }
+    html.push_str("</article>\n");
+    html
+}
+
+const STYLE: &str = "body { max-width: 40em; margin: 2em auto; padding: 0 1em; font-family: serif; line-height: 1.5; }
+img { max-width: 100%; }
+figcaption, .meta { color: #666; font-size: 0.9em; }";
+
+fn to_html(title: &str, documents: &[Document]) -> String {
+    let nested = documents.len() > 1;
+    let mut body = String::new();
+
+    if nested {
+        body.push_str(&format!("<h1>{}</h1>\n<nav><ol>\n", escape(title)));
+        for (i, document) in documents.iter().enumerate() {
+            body.push_str(&format!(
+                "<li><a href=\"#artikel-{}\">{}</a></li>\n",
+                i + 1,
+                escape(&document.article.title)
+            ));
+        }
+        body.push_str("</ol></nav>\n");
+    }
+    for (i, document) in documents.iter().enumerate() {
+        let (heading, id) = if nested {
+            (2, Some(format!("artikel-{}", i + 1)))
+        } else {
+            (1, None)
+        };
+        body.push_str(&article_html(document, heading, id));
+    }
+
+    format!(
+        "<!DOCTYPE html>\n<html lang=\"nl\">\n<head>\n<meta charset=\"utf-8\"/>\n<title>{}</title>\n<style>\n{}\n</style>\n</head>\n<body>\n{}</body>\n</html>\n",
+        escape(title),
+        STYLE,
+        body
+    )
+}
+
+fn xhtml(title: &str, body: &str) -> String {
+    format!(
+        "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<!DOCTYPE html>\n<html xmlns=\"http://www.w3.org/1999/xhtml\" xmlns:epub=\"http://www.idpf.org/2007/ops\" xml:lang=\"nl\" lang=\"nl\">\n<head>\n<meta charset=\"utf-8\"/>\n<title>{}</title>\n<style>\n{}\n</style>\n</head>\n<body>\n{}</body>\n</html>\n",
+        escape(title),
+        STYLE,
+        body
+    )
+}
+
+/// Writes an EPUB 3 book with a chapter for every article. Images are
+/// referenced by their URL rather than embedded.
+fn write_epub(
+    path: &Path,
+    title: &str,
+    documents: &[Document],
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut zip = ZipWriter::new(File::create(path)?);
+    let stored = SimpleFileOptions::default().compression_method(CompressionMethod::Stored);
+    let deflated = SimpleFileOptions::default();
+
+    // The mimetype has to come first and uncompressed
+    zip.start_file("mimetype", stored)?;
+    zip.write_all(b"application/epub+zip")?;
+
+    zip.start_file("META-INF/container.xml", deflated)?;
+    zip.write_all(
+        b"<?xml version=\"1.0\" encoding=\"utf-8\"?>
+<container version=\"1.0\" xmlns=\"urn:oasis:names:tc:opendocument:xmlns:container\">
+<rootfiles><rootfile full-path=\"OEBPS/content.opf\" media-type=\"application/oebps-package+xml\"/></rootfiles>
+</container>
+",
+    )?;
+
+    let mut manifest = String::from(
+        "<item id=\"nav\" href=\"nav.xhtml\" media-type=\"application/xhtml+xml\" properties=\"nav\"/>\n",
+    );
+    let mut spine = String::new();
+    let mut toc = String::new();
+
+    for (i, document) in documents.iter().enumerate() {
+        let name = format!("artikel-{}.xhtml", i + 1);
+        let remote = document
+            .blocks
+            .iter()
+            .any(|block| matches!(block, Block::Image(_)));
+        manifest.push_str(&format!(
+            "<item id=\"artikel-{}\" href=\"{}\" media-type=\"application/xhtml+xml\"{}/>\n",
+            i + 1,
+            name,
+            if remote {
+                " properties=\"remote-resources\""
+            } else {
+                ""
+            }
+        ));
+        spine.push_str(&format!("<itemref idref=\"artikel-{}\"/>\n", i + 1));
+        toc.push_str(&format!(
+            "<li><a href=\"{}\">{}</a></li>\n",
+            name,
+            escape(&document.article.title)
+        ));
+
+        zip.start_file(format!("OEBPS/{}", name), deflated)?;
+        zip.write_all(xhtml(&document.article.title, &article_html(document, 1, None)).as_bytes())?;
+    }
+
+    zip.start_file("OEBPS/nav.xhtml", deflated)?;
+    let nav = format!(
+        "<nav epub:type=\"toc\" id=\"toc\">\n<h1>{}</h1>\n<ol>\n{}</ol>\n</nav>\n",
+        escape(title),
+        toc
+    );
+    zip.write_all(xhtml(title, &nav).as_bytes())?;
+
+    let identifier = match documents {
+        [document] => document.article.href.clone(),
+        _ => format!("nos-cli-{}", Utc::now().format("%Y%m%d%H%M%S")),
+    };
+    zip.start_file("OEBPS/content.opf", deflated)?;
+    let package = format!(
+        "<?xml version=\"1.0\" encoding=\"utf-8\"?>
+<package xmlns=\"http://www.idpf.org/2007/opf\" version=\"3.0\" unique-identifier=\"id\" xml:lang=\"nl\">
+<metadata xmlns:dc=\"http://purl.org/dc/elements/1.1/\">
+<dc:identifier id=\"id\">{}</dc:identifier>
+<dc:title>{}</dc:title>
+<dc:language>nl</dc:language>
+<dc:publisher>NOS</dc:publisher>
+<meta property=\"dcterms:modified\">{}</meta>
+</metadata>
+<manifest>
+{}</manifest>
+<spine>
+<itemref idref=\"nav\"/>
+{}</spine>
+</package>
+",
+        escape(&identifier),
+        escape(title),
+        Utc::now().format("%Y-%m-%dT%H:%M:%SZ"),
+        manifest,
+        spine
+    );
+    zip.write_all(package.as_bytes())?;
+
+    zip.finish()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scrape::Image;
+
+    #[test]
+    fn test_to_markdown() {
+        let document = Document {
+            article: Article {
+                title: String::from("Storm <op komst>"),
+                href: String::from("https://nos.nl/artikel/1-storm"),
+                datetime: String::from("12:34"),
+                published: None,
+                category: String::from("binnenland"),
+                source: String::from("nos"),
+            },
+            blocks: vec![
+                Block::Text(String::from("Het gaat hard waaien.")),
+                Block::Linked {
+                    text: String::from("Lees [meer] over de storm."),
+                    links: vec![Link {
+                        text: String::from("de storm"),
+                        href: String::from("https://nos.nl/artikel/2-storm"),
+                    }],
+                },
+                Block::Heading {
+                    heading: String::from("Code oranje"),
+                },
+                Block::Image(Image {
+                    url: String::from("https://nos.nl/data/storm.jpg"),
+                    caption: String::from("Bomen"),
+                }),
+            ],
+        };
+
+        let markdown = to_markdown("Export", std::slice::from_ref(&document));
+        assert_eq!(
+            markdown,
+            "# Storm \\<op komst\\>\n\n*binnenland · 12:34 · [Bron](https://nos.nl/artikel/1-storm)*\n\nHet gaat hard waaien.\n\nLees \\[meer\\] over [de storm](https://nos.nl/artikel/2-storm).\n\n## Code oranje\n\n![Bomen](https://nos.nl/data/storm.jpg)\n\n*Bomen*\n\n"
+        );
+        assert_eq!(
+            escape_markdown("1. *Alleen* A_B"),
+            "1\\. \\*Alleen\\* A\\_B"
+        );
+        let html = to_html("Export", &[document]);
+        assert!(html.contains("<h1>Storm &lt;op komst&gt;</h1>"));
+        assert!(html.contains("over <a href=\"https://nos.nl/artikel/2-storm\">de storm</a>."));
+    }
+}
@@ -0,0 +1,375 @@
+use std::{
+    collections::{BTreeSet, HashSet, VecDeque},
+    env,
+    fmt::Write,
+    io::Cursor,
+    sync::mpsc::{self, Receiver, Sender},
+    thread,
+};
+
+use base64::{Engine, engine::general_purpose::STANDARD};
+use image::{DynamicImage, ImageFormat, RgbImage, imageops::FilterType};
+use termion::{color, style};
+
+/// Size of a terminal cell in pixels, for when the terminal doesn't report it.
+const DEFAULT_CELL_SIZE: (f64, f64) = (8.0, 16.0);
+
+/// Largest payload of a single kitty graphics escape sequence.
+const KITTY_CHUNK_SIZE: usize = 4096;
+
+/// Number of rendered images kept, so opening an article again doesn't
+/// fetch its images again.
+const CACHE_SIZE: usize = 50;
+
+/// How images in articles are drawn.
+#[derive(Clone, Copy, Debug, Default, PartialEq, clap::ValueEnum)]
+pub enum ImageProtocol {
+    /// Pick one based on the terminal
+    #[default]
+    Auto,
+    /// The kitty graphics protocol, also supported by Ghostty and WezTerm
+    Kitty,
+    /// Inline images of iTerm2
+    Iterm,
+    Sixel,
+    /// Unicode half blocks in 24-bit color
+    Blocks,
+    /// Only show the caption
+    None,
+}
+
+impl ImageProtocol {
+    /// Resolves `Auto` by looking at the environment the terminal sets.
+    pub fn detect(self) -> Self {
+        if self != ImageProtocol::Auto {
+            return self;
+        }
+        let var = |name: &str| env::var(name).unwrap_or_default();
+        let term = var("TERM");
+        let term_program = var("TERM_PROGRAM");
+
+        if env::var_os("KITTY_WINDOW_ID").is_some()
+            || term == "xterm-kitty"
+            || term_program == "ghostty"
+        {
+            ImageProtocol::Kitty
+        } else if term_program == "iTerm.app"
+            || term_program == "WezTerm"
+            || var("LC_TERMINAL") == "iTerm2"
+        {
+            ImageProtocol::Iterm
+        } else if ["foot", "mlterm", "yaft", "contour"]
+            .iter()
+            .any(|name| term.starts_with(name))
+        {
+            ImageProtocol::Sixel
+        } else if matches!(var("COLORTERM").as_str(), "truecolor" | "24bit") {
+            ImageProtocol::Blocks
+        } else {
+            ImageProtocol::None
+        }
+    }
+}
+
+/// An image prepared for the article view.
+#[derive(Clone)]
+pub enum Graphic {
+    /// Lines of colored text
+    Lines(Vec<String>),
+    /// An escape sequence that draws the image at the cursor position
+    Escape { data: String, rows: usize },
+}
+
+/// Fetches an image and converts it for `protocol`, at most `max_cols` wide
+/// and `max_rows` high.
+pub fn render(
+    url: &str,
+    protocol: ImageProtocol,
+    max_cols: usize,
+    max_rows: usize,
+) -> Result<Graphic, Box<dyn std::error::Error>> {
+    if matches!(protocol, ImageProtocol::None | ImageProtocol::Auto) {
+        return Err("Images are turned off".into());
+    }
+    let bytes = reqwest::blocking::get(url)?.error_for_status()?.bytes()?;
+    let image = image::load_from_memory(&bytes)?;
+
+    let (cell_width, cell_height) = cell_size();
+    let (cols, rows) = fit(&image, cell_width, cell_height, max_cols, max_rows);
+    let pixel_width = (cols as f64 * cell_width) as u32;
+    let pixel_height = (rows as f64 * cell_height) as u32;
+
+    let graphic = match protocol {
+        ImageProtocol::Kitty => {
+            let png = to_png(&image.resize_exact(pixel_width, pixel_height, FilterType::Triangle))?;
+            Graphic::Escape {
+                data: kitty_escape(&png, cols, rows),
+                rows,
+            }
+        }
+        ImageProtocol::Iterm => {
+            let png = to_png(&image.resize_exact(pixel_width, pixel_height, FilterType::Triangle))?;
+            Graphic::Escape {
+                data: format!(
+                    "\x1b]1337;File=inline=1;width={};height={};preserveAspectRatio=0:{}\x07",
+                    cols,
+                    rows,
+                    STANDARD.encode(png)
+                ),
+                rows,
+            }
+        }
+        ImageProtocol::Sixel => {
+            let pixels = image
+                .resize_exact(pixel_width, pixel_height, FilterType::Triangle)
+                .to_rgb8();
+            Graphic::Escape {
+                data: sixel_escape(&pixels),
+                rows,
+            }
+        }
+        _ => {
+            let pixels = image
+                .resize_exact(cols as u32, rows as u32 * 2, FilterType::Triangle)
+                .to_rgb8();
+            Graphic::Lines(half_blocks(&pixels))
+        }
+    };
+    Ok(graphic)
+}
+
+/// An image by URL and the room it was rendered for.
+type Key = (String, usize, usize);
+
+/// Renders images on background threads and keeps the latest ones, so an
+/// article shows up right away and its images fill in as they arrive.
+pub struct ImageLoader {
+    protocol: ImageProtocol,
+    /// Rendered images, oldest first, `None` when one couldn't be shown
+    cache: VecDeque<(Key, Option<Graphic>)>,
+    pending: HashSet<Key>,
+    sender: Sender<(Key, Option<Graphic>)>,
+    receiver: Receiver<(Key, Option<Graphic>)>,
+}
+
+impl ImageLoader {
+    pub fn new(protocol: ImageProtocol) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        ImageLoader {
+            protocol,
+            cache: VecDeque::new(),
+            pending: HashSet::new(),
+            sender,
+            receiver,
+        }
+    }
+
+    /// The image when it's rendered, `Some(None)` when it can't be shown.
+    /// Returns `None` and starts rendering it otherwise.
+    pub fn get(&mut self, url: &str, max_cols: usize, max_rows: usize) -> Option<Option<&Graphic>> {
+        if matches!(self.protocol, ImageProtocol::None | ImageProtocol::Auto) {
+            return Some(None);
+        }
+        let key = (url.to_string(), max_cols, max_rows);
+        if let Some(i) = self.cache.iter().position(|(cached, _)| *cached == key) {
+            return Some(self.cache[i].1.as_ref());
+        }
+        if self.pending.insert(key.clone()) {
+            let sender = self.sender.clone();
+            let protocol = self.protocol;
+            thread::spawn(move || {
+                let graphic = render(&key.0, protocol, key.1, key.2).ok();
+                let _ = sender.send((key, graphic));
+            });
+        }
+        None
+    }
+
+    /// Stores the images that were rendered since. Returns whether there were any.
+    pub fn receive(&mut self) -> bool {
+        let mut received = false;
+        while let Ok((key, graphic)) = self.receiver.try_recv() {
+            self.pending.remove(&key);
+            self.cache.push_back((key, graphic));
+            if self.cache.len() > CACHE_SIZE {
+                self.cache.pop_front();
+            }
+            received = true;
+        }
+        received
+    }
+
+    pub fn is_loading(&self) -> bool {
+        !self.pending.is_empty()
+    }
+}
+
+fn cell_size() -> (f64, f64) {
+    match (termion::terminal_size(), termion::terminal_size_pixels()) {
+        (Ok((cols, rows)), Ok((width, height))) if cols > 0 && rows > 0 && width > 0 => {
+            (width as f64 / cols as f64, height as f64 / rows as f64)
+        }
+        _ => DEFAULT_CELL_SIZE,
+    }
+}
+
+/// Number of columns and rows the image takes up, keeping its aspect ratio.
+fn fit(
+    image: &DynamicImage,
+    cell_width: f64,
+    cell_height: f64,
+    max_cols: usize,
+    max_rows: usize,
+) -> (usize, usize) {
+    let aspect = image.height() as f64 / image.width().max(1) as f64;
+    // Small images aren't scaled up
+    let natural_cols = (image.width() as f64 / cell_width).ceil() as usize;
+    let mut cols = natural_cols.clamp(1, max_cols.max(1));
+    let mut rows = (cols as f64 * cell_width * aspect / cell_height).ceil() as usize;
+    if rows > max_rows {
+        rows = max_rows.max(1);
+        cols = ((rows as f64 * cell_height / (aspect * cell_width)) as usize).max(1);
+    }
+    (cols, rows.max(1))
+}
+
+fn to_png(image: &DynamicImage) -> Result<Vec<u8>, image::ImageError> {
+    let mut png = Vec::new();
+    image.write_to(&mut Cursor::new(&mut png), ImageFormat::Png)?;
+    Ok(png)
+}
+
+/// Transmits and shows a PNG in one go, split into chunks. The cursor stays
+/// where it is and the terminal is asked not to respond.
+fn kitty_escape(png: &[u8], cols: usize, rows: usize) -> String {
+    let encoded = STANDARD.encode(png);
+    let chunks: Vec<&[u8]> = encoded.as_bytes().chunks(KITTY_CHUNK_SIZE).collect();
+    let mut data = String::new();
+
+    for (i, chunk) in chunks.iter().enumerate() {
+        let more = u8::from(i + 1 < chunks.len());
+        let chunk = std::str::from_utf8(chunk).unwrap_or_default();
+        if i == 0 {
+            let _ = write!(
+                data,
+                "\x1b_Ga=T,f=100,c={},r={},C=1,q=2,m={};{}\x1b\\",
+                cols, rows, more, chunk
+            );
+        } else {
+            let _ = write!(data, "\x1b_Gm={};{}\x1b\\", more, chunk);
+        }
+    }
+    data
+}
+
+/// Encodes the pixels as sixels, with the colors reduced to a 6x6x6 cube.
+fn sixel_escape(pixels: &RgbImage) -> String {
+    let (width, height) = pixels.dimensions();
+    let palette_index = |x: u32, y: u32| {
+        let [r, g, b] = pixels.get_pixel(x, y).0;
+        let level = |c: u8| (c as usize * 5 + 127) / 255;
+        level(r) * 36 + level(g) * 6 + level(b)
+    };
+
+    let mut data = format!("\x1bPq\"1;1;{};{}", width, height);
+    for i in 0..216 {
+        let percent = |level: usize| level * 100 / 5;
+        let _ = write!(
+            data,
+            "#{};2;{};{};{}",
+            i,
+            percent(i / 36),
+            percent(i / 6 % 6),
+            percent(i % 6)
+        );
+    }
+
+    for band in (0..height).step_by(6) {
+        let band_rows = band..(band + 6).min(height);
+        let colors: BTreeSet<usize> = band_rows
+            .clone()
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .map(|(x, y)| palette_index(x, y))
+            .collect();
+
+        for color in colors {
+            let _ = write!(data, "#{}", color);
+            let mut run: Option<(char, usize)> = None;
+            for x in 0..width {
+                let bits = band_rows
+                    .clone()
+                    .filter(|&y| palette_index(x, y) == color)
+                    .fold(0, |bits, y| bits | 1 << (y - band));
+                let sixel = char::from(63 + bits as u8);
+                run = match run {
+                    Some((c, n)) if c == sixel => Some((c, n + 1)),
+                    Some((c, n)) => {
+                        push_sixel_run(&mut data, c, n);
+                        Some((sixel, 1))
+                    }
+                    None => Some((sixel, 1)),
+                };
+            }
+            if let Some((c, n)) = run {
+                push_sixel_run(&mut data, c, n);
+            }
+            data.push('$');
+        }
+        data.push('-');
+    }
+
+    data.push_str("\x1b\\");
+    data
+}
+
+fn push_sixel_run(data: &mut String, sixel: char, count: usize) {
+    if count > 3 {
+        let _ = write!(data, "!{}{}", count, sixel);
+    } else {
+        data.extend(std::iter::repeat_n(sixel, count));
+    }
+}
+
+/// Draws two pixels per cell with the upper half block, the top one as the
+/// foreground and the bottom one as the background color.
+fn half_blocks(pixels: &RgbImage) -> Vec<String> {
+    let (width, height) = pixels.dimensions();
+    let rgb = |x: u32, y: u32| {
+        let [r, g, b] = pixels.get_pixel(x, y).0;
+        color::Rgb(r, g, b)
+    };
+
+    (0..height)
+        .step_by(2)
+        .map(|y| {
+            let mut line = String::new();
+            for x in 0..width {
+                let bottom = if y + 1 < height {
+                    rgb(x, y + 1)
+                } else {
+                    rgb(x, y)
+                };
+                let _ = write!(line, "{}{}▀", color::Fg(rgb(x, y)), color::Bg(bottom));
+            }
+            line.push_str(style::Reset.as_ref());
+            line
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fit() {
+        let image = DynamicImage::new_rgb8(1600, 900);
+        // Limited by the width
+        assert_eq!(fit(&image, 10.0, 20.0, 80, 40), (80, 23));
+        // Limited by the height, the width shrinks along
+        assert_eq!(fit(&image, 10.0, 20.0, 80, 10), (35, 10));
+        // Small images keep their size
+        let small = DynamicImage::new_rgb8(100, 50);
+        assert_eq!(fit(&small, 10.0, 20.0, 80, 40), (10, 3));
+    }
+}
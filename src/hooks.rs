@@ -0,0 +1,132 @@
+use std::{
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    thread,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{scrape::Article, store};
+
+/// Shell commands to run when something happens, read from `hooks.toml` in
+/// the config directory.
+#[derive(Default, Deserialize)]
+#[serde(default)]
+pub struct Hooks {
+    /// An article showed up that wasn't listed before
+    pub on_new_article: Option<String>,
+    pub on_open_article: Option<String>,
+    /// An article was added to the bookmarks
+    pub on_bookmark: Option<String>,
+}
+
+/// What a hook gets on stdin.
+#[derive(Serialize)]
+struct Payload<'a> {
+    event: &'a str,
+    #[serde(flatten)]
+    article: &'a Article,
+}
+
+impl Hooks {
+    /// Loads the hooks at `path`, or from `hooks.toml` in the config directory
+    /// when no path is given. Without a file no hooks are run.
+    pub fn load(path: Option<&Path>) -> Result<Self, Box<dyn std::error::Error>> {
+        let path = match path {
+            Some(path) => path.to_path_buf(),
+            None => match default_path().filter(|path| path.exists()) {
+                Some(path) => path,
+                None => return Ok(Hooks::default()),
+            },
+        };
+        let contents = fs::read_to_string(&path)
+            .map_err(|e| format!("Couldn't read {}: {}", path.display(), e))?;
+        let hooks = toml::from_str(&contents)
+            .map_err(|e| format!("Invalid hooks {}: {}", path.display(), e))?;
+        Ok(hooks)
+    }
+
+    pub fn new_article(&self, article: &Article) -> std::io::Result<()> {
+        run(self.on_new_article.as_deref(), "new_article", article)
+    }
+
+    pub fn open_article(&self, article: &Article) -> std::io::Result<()> {
+        run(self.on_open_article.as_deref(), "open_article", article)
+    }
+
+    pub fn bookmark(&self, article: &Article) -> std::io::Result<()> {
+        run(self.on_bookmark.as_deref(), "bookmark", article)
+    }
+}
+
+/// Starts `command` with `sh -c` without waiting for it. The article is
+/// passed in `NOS_*` environment variables and as JSON on stdin.
+fn run(command: Option<&str>, event: &str, article: &Article) -> std::io::Result<()> {
+    let Some(command) = command else {
+        return Ok(());
+    };
+    let payload = serde_json::to_vec(&Payload { event, article })?;
+    let mut child = Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .envs(environment(event, article))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+
+    let mut stdin = child.stdin.take();
+    thread::spawn(move || {
+        // Commands that don't read stdin close it early, that's fine
+        if let Some(stdin) = stdin.as_mut() {
+            let _ = stdin.write_all(&payload);
+        }
+        drop(stdin);
+        child.wait()
+    });
+    Ok(())
+}
+
+/// The environment variables hooks and notification commands get.
+pub fn environment(event: &str, article: &Article) -> Vec<(&'static str, String)> {
+    vec![
+        ("NOS_EVENT", event.to_string()),
+        ("NOS_TITLE", article.title.clone()),
+        ("NOS_URL", article.href.clone()),
+        ("NOS_TIME", article.datetime.clone()),
+        ("NOS_CATEGORY", article.category.clone()),
+    ]
+}
+
+/// `~/.config/nos-cli/hooks.toml`
+fn default_path() -> Option<PathBuf> {
+    store::config_dir().map(|dir| dir.join("hooks.toml"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_payload() {
+        let article = Article {
+            title: String::from("Storm op komst"),
+            href: String::from("https://nos.nl/artikel/1-storm"),
+            datetime: String::from("12:34"),
+            published: None,
+            category: String::from("binnenland"),
+            source: String::from("nos"),
+        };
+        let payload: serde_json::Value = serde_json::to_value(Payload {
+            event: "bookmark",
+            article: &article,
+        })
+        .unwrap();
+        assert_eq!(payload["event"], "bookmark");
+        assert_eq!(payload["title"], "Storm op komst");
+        assert_eq!(payload["href"], "https://nos.nl/artikel/1-storm");
+        assert_eq!(payload["category"], "binnenland");
+    }
+}
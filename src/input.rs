@@ -1,5 +1,14 @@
-use termion::event::Key;
+use std::time::Duration;
 
+use termion::event::{Key, MouseButton, MouseEvent};
+
+/// How long to wait for the next key of a multi-key sequence (like `gg`).
+pub const SEQUENCE_TIMEOUT: Duration = Duration::from_millis(1000);
+
+/// Number of rows a single scroll wheel step moves.
+pub const SCROLL_LINES: usize = 3;
+
+#[derive(Debug, PartialEq)]
 pub enum Action {
     Quit,
     GoBack,
@@ -9,27 +18,207 @@ pub enum Action {
     GotoBottom,
     PageUp,
     PageDown,
+    HalfPageUp,
+    HalfPageDown,
+    CenterScreen,
+    ScrollToTop,
+    ScrollToBottom,
+    ScreenTop,
+    ScreenMiddle,
+    ScreenBottom,
+    ScrollUp,
+    ScrollDown,
+    /// Left click on the given (zero-based) screen column and row
+    Click(usize, usize),
     EnterArticle,
     Search,
     Reset,
+    Refresh,
+    ToggleHideRead,
+    ToggleBookmark,
+    /// Play the video or audio with the given number (the count) of the open article
+    PlayMedia,
+    /// Move the focus between the list and the article when they're side by side
+    ToggleFocus,
+    /// Go to the next tab, or to the tab with the given number (the count)
+    NextTab,
+    PreviousTab,
     CommandMode,
     None,
 }
 
+/// Turns keystrokes into actions, keeping track of a count prefix (`5j`)
+/// and of keys that start a longer sequence (`gg`, `zz`).
+pub struct KeyParser {
+    count: Option<usize>,
+    pending: Option<char>,
+}
+
+impl KeyParser {
+    pub fn new() -> Self {
+        KeyParser {
+            count: None,
+            pending: None,
+        }
+    }
+
+    /// Feeds a key to the parser. Returns `None` while a sequence is still
+    /// incomplete, otherwise the action together with its count, if any.
+    pub fn feed(&mut self, key: Key) -> Option<(Action, Option<usize>)> {
+        if let Some(prefix) = self.pending.take() {
+            let action = match (prefix, key) {
+                ('g', Key::Char('g')) => Action::GotoTop,
+                ('g', Key::Char('t')) => Action::NextTab,
+                ('g', Key::Char('T')) => Action::PreviousTab,
+                ('z', Key::Char('z')) => Action::CenterScreen,
+                ('z', Key::Char('t')) => Action::ScrollToTop,
+                ('z', Key::Char('b')) => Action::ScrollToBottom,
+                _ => {
+                    self.clear();
+                    return Some((Action::None, None));
+                }
+            };
+            return Some((action, self.count.take()));
+        }
+
+        match key {
+            Key::Char(c @ '1'..='9') => {
+                self.push_digit(c);
+                return None;
+            }
+            Key::Char('0') if self.count.is_some() => {
+                self.push_digit('0');
+                return None;
+            }
+            Key::Char(c @ ('g' | 'z')) => {
+                self.pending = Some(c);
+                return None;
+            }
+            Key::Esc if self.is_pending() => {
+                self.clear();
+                return Some((Action::None, None));
+            }
+            _ => (),
+        }
+
+        Some((handle_input(key), self.count.take()))
+    }
+
+    /// Whether a count or an incomplete sequence has been typed.
+    pub fn is_pending(&self) -> bool {
+        self.count.is_some() || self.pending.is_some()
+    }
+
+    /// The keys typed so far, for showing in the status bar.
+    pub fn pending_keys(&self) -> String {
+        let mut keys = self.count.map(|c| c.to_string()).unwrap_or_default();
+        if let Some(c) = self.pending {
+            keys.push(c);
+        }
+        keys
+    }
+
+    pub fn clear(&mut self) {
+        self.count = None;
+        self.pending = None;
+    }
+
+    fn push_digit(&mut self, digit: char) {
+        let digit = digit.to_digit(10).unwrap() as usize;
+        let count = self.count.unwrap_or(0);
+        self.count = Some(count.saturating_mul(10).saturating_add(digit));
+    }
+}
+
 pub fn handle_input(key: Key) -> Action {
     match key {
         Key::Char('q') | Key::Esc => Action::Quit,
         Key::Char('k') | Key::Up => Action::MoveUp,
         Key::Char('j') | Key::Down => Action::MoveDown,
-        Key::Char('g') => Action::GotoTop,
         Key::Char('G') => Action::GotoBottom,
+        Key::Char('H') => Action::ScreenTop,
+        Key::Char('M') => Action::ScreenMiddle,
+        Key::Char('L') => Action::ScreenBottom,
         Key::Char('b') => Action::GoBack,
         Key::Ctrl('f') | Key::PageDown => Action::PageDown,
         Key::Ctrl('b') | Key::PageUp => Action::PageUp,
+        Key::Ctrl('d') => Action::HalfPageDown,
+        Key::Ctrl('u') => Action::HalfPageUp,
         Key::Char('\n') | Key::Char('i') => Action::EnterArticle,
         Key::Char('/') => Action::Search,
         Key::Char('r') => Action::Reset,
+        Key::Char('R') => Action::Refresh,
+        Key::Char('u') => Action::ToggleHideRead,
+        Key::Char('m') => Action::ToggleBookmark,
+        Key::Char('p') => Action::PlayMedia,
+        Key::Char('\t') => Action::ToggleFocus,
         Key::Char(':') => Action::CommandMode,
         _ => Action::None,
     }
 }
+
+pub fn handle_mouse(event: MouseEvent) -> Action {
+    match event {
+        MouseEvent::Press(MouseButton::Left, x, y) => Action::Click(x as usize - 1, y as usize - 1),
+        MouseEvent::Press(MouseButton::WheelUp, _, _) => Action::ScrollUp,
+        MouseEvent::Press(MouseButton::WheelDown, _, _) => Action::ScrollDown,
+        _ => Action::None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn feed_all(parser: &mut KeyParser, keys: &str) -> Option<(Action, Option<usize>)> {
+        let mut result = None;
+        for c in keys.chars() {
+            result = parser.feed(Key::Char(c));
+        }
+        result
+    }
+
+    #[test]
+    fn test_count_prefix() {
+        let mut parser = KeyParser::new();
+        assert_eq!(feed_all(&mut parser, "5"), None);
+        assert_eq!(parser.pending_keys(), "5");
+        assert_eq!(
+            feed_all(&mut parser, "0j"),
+            Some((Action::MoveDown, Some(50)))
+        );
+        assert!(!parser.is_pending());
+    }
+
+    #[test]
+    fn test_sequences() {
+        let mut parser = KeyParser::new();
+        assert_eq!(feed_all(&mut parser, "gg"), Some((Action::GotoTop, None)));
+        assert_eq!(
+            feed_all(&mut parser, "zz"),
+            Some((Action::CenterScreen, None))
+        );
+        assert_eq!(
+            feed_all(&mut parser, "10G"),
+            Some((Action::GotoBottom, Some(10)))
+        );
+        assert_eq!(
+            feed_all(&mut parser, "2gt"),
+            Some((Action::NextTab, Some(2)))
+        );
+        assert_eq!(
+            feed_all(&mut parser, "gT"),
+            Some((Action::PreviousTab, None))
+        );
+        assert_eq!(feed_all(&mut parser, "gx"), Some((Action::None, None)));
+    }
+
+    #[test]
+    fn test_escape_cancels_pending() {
+        let mut parser = KeyParser::new();
+        feed_all(&mut parser, "3z");
+        assert_eq!(parser.feed(Key::Esc), Some((Action::None, None)));
+        assert!(!parser.is_pending());
+        assert_eq!(parser.feed(Key::Esc), Some((Action::Quit, None)));
+    }
+}
@@ -0,0 +1,198 @@
+use std::collections::HashSet;
+
+use chrono::{DateTime, FixedOffset};
+use scraper::Html;
+use termion::{color, style};
+
+use crate::{
+    profile::LiveblogProfile,
+    renderer,
+    scrape::{self, Block},
+    util,
+};
+
+/// One timestamped update in a liveblog.
+#[derive(Clone)]
+pub struct Entry {
+    /// Identifies the entry between polls, the element id when there is one
+    pub id: String,
+    pub time: String,
+    pub published: Option<DateTime<FixedOffset>>,
+    pub title: String,
+    pub paragraphs: Vec<String>,
+}
+
+pub fn is_liveblog(url: &str, profile: &LiveblogProfile) -> bool {
+    url.contains(&profile.url_pattern)
+}
+
+/// Fetches the entries of a liveblog, newest first.
+pub fn get_entries(
+    url: &str,
+    profile: &LiveblogProfile,
+) -> Result<Vec<Entry>, Box<dyn std::error::Error>> {
+    let body = reqwest::blocking::get(url)?.text()?;
+    parse_entries(&Html::parse_document(&body), profile)
+}
+
+/// CSS selectors used on liveblog pages, checked by `doctor`.
+pub fn selectors(profile: &LiveblogProfile) -> Vec<String> {
+    let entry = &profile.entry;
+    vec![
+        entry.clone(),
+        scrape::nested_selector(entry, &profile.datetime),
+        scrape::nested_selector(entry, &profile.title),
+        scrape::nested_selector(entry, &profile.text),
+    ]
+}
+
+pub fn parse_entries(
+    document: &Html,
+    profile: &LiveblogProfile,
+) -> Result<Vec<Entry>, Box<dyn std::error::Error>> {
+    let entry_selector = scrape::selector(&profile.entry)?;
+    let datetime_selector = scrape::selector(&profile.datetime)?;
+    let title_selector = scrape::selector(&profile.title)?;
+    let text_selector = scrape::selector(&profile.text)?;
+
+    let mut entries = Vec::new();
+
+    for element in document.select(&entry_selector) {
+        let time_element = element.select(&datetime_selector).next();
+        // Anything without a timestamp is part of the page around the entries
+        let Some(time_element) = time_element else {
+            continue;
+        };
+        let time = util::element_to_text(time_element);
+        let published = time_element
+            .value()
+            .attr("datetime")
+            .and_then(util::parse_timestamp);
+
+        let title = element
+            .select(&title_selector)
+            .next()
+            .map(util::element_to_text)
+            .unwrap_or_default();
+        let paragraphs = element
+            .select(&text_selector)
+            .map(util::element_to_text)
+            .collect();
+
+        let id = match element.value().attr("id") {
+            Some(id) => id.to_string(),
+            None => format!("{} {}", time, title),
+        };
+
+        entries.push(Entry {
+            id,
+            time,
+            published,
+            title,
+            paragraphs,
+        });
+    }
+
+    // Entries without a timestamp keep their place at the end
+    entries.sort_by_key(|e| std::cmp::Reverse(e.published));
+
+    Ok(entries)
+}
+
+/// The entries as an article body, for exporting.
+pub fn entries_to_blocks(entries: &[Entry]) -> Vec<Block> {
+    let mut blocks = Vec::new();
+    for entry in entries {
+        blocks.push(Block::Heading {
+            heading: format!("{} {}", entry.time, entry.title),
+        });
+        blocks.extend(entry.paragraphs.iter().cloned().map(Block::Text));
+    }
+    blocks
+}
+
+/// Formats the entries as article lines, marking the ones in `new_ids`.
+pub fn entries_to_lines(entries: &[Entry], new_ids: &HashSet<String>, width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+
+    for entry in entries {
+        let (badge, badge_width) = if new_ids.contains(&entry.id) {
+            let badge = format!(
+                "{}{}{} ",
+                color::Fg(color::Yellow),
+                renderer::NEW_BADGE,
+                color::Fg(color::Reset)
+            );
+            (badge, renderer::NEW_BADGE.len() + 1)
+        } else {
+            (String::new(), 0)
+        };
+        // Wrapped after blanks that make room for the badge
+        let indent = " ".repeat(badge_width);
+        let heading = format!("{} {}", entry.time, entry.title);
+        let options = textwrap::Options::new(width).initial_indent(&indent);
+        for (i, line) in textwrap::wrap(&heading, options).iter().enumerate() {
+            let line = if i == 0 { &line[badge_width..] } else { line };
+            let badge = if i == 0 { badge.as_str() } else { "" };
+            lines.push(format!(
+                "\r\n{}{}{}{}",
+                badge,
+                style::Bold,
+                line,
+                style::Reset
+            ));
+        }
+
+        for paragraph in &entry.paragraphs {
+            for line in textwrap::wrap(paragraph, width) {
+                lines.push(format!("\r\n{}", line));
+            }
+        }
+        lines.push("\r\n".to_string());
+    }
+
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::profile::SiteProfile;
+
+    #[test]
+    fn test_parse_entries() {
+        let body = r#"<html><body><main>
+            <h1>Liveblog</h1>
+            <ul>
+                <li id="UPDATE-1"><time datetime="2025-07-10T10:00:00+0200">10:00</time><h2>Begin</h2><p>Eerste update.</p></li>
+                <li id="UPDATE-2"><time datetime="2025-07-10T11:00:00+0200">11:00</time><h2>Later</h2><p>Tweede update.</p><p>Meer tekst.</p></li>
+                <li id="menu">Geen update</li>
+            </ul>
+        </main></body></html>"#;
+
+        let document = Html::parse_document(body);
+        let entries = parse_entries(&document, &SiteProfile::default().liveblog).unwrap();
+        let ids: Vec<&str> = entries.iter().map(|e| e.id.as_str()).collect();
+        assert_eq!(ids, ["UPDATE-2", "UPDATE-1"]);
+        assert_eq!(entries[0].time, "11:00");
+        assert_eq!(entries[0].title, "Later");
+        assert_eq!(entries[0].paragraphs, ["Tweede update.", "Meer tekst."]);
+    }
+
+    #[test]
+    fn test_entries_to_lines() {
+        let entry = Entry {
+            id: String::from("UPDATE-1"),
+            time: String::from("10:00"),
+            published: None,
+            title: String::from("Kabinet valt over stikstof"),
+            paragraphs: vec![String::from("Eerste update.")],
+        };
+        let new_ids = HashSet::from([entry.id.clone()]);
+        let lines = entries_to_lines(&[entry], &new_ids, 20);
+        let bold = |text: &str| format!("{}{}{}", style::Bold, text, style::Reset);
+        // The badge takes up the first 6 columns
+        assert!(lines[0].ends_with(&bold("10:00 Kabinet")));
+        assert_eq!(lines[1], format!("\r\n{}", bold("valt over stikstof")));
+    }
+}
@@ -1,26 +1,151 @@
 mod app;
+mod archive;
 mod args;
+mod daemon;
+mod digest;
+mod doctor;
+mod export;
+mod graphics;
+mod hooks;
 mod input;
+mod liveblog;
+mod nos;
+mod profile;
 mod renderer;
+mod rss;
 mod scrape;
+mod source;
+mod sport;
 mod state;
+mod store;
 mod util;
+mod watchlist;
 
 extern crate termion;
 
-use crate::app::App;
+use crate::{
+    app::App,
+    archive::{Archive, Query},
+    args::Command,
+    export::ExportFormat,
+    hooks::Hooks,
+    profile::SiteProfile,
+    source::Sources,
+    watchlist::Watchlist,
+};
 use clap::Parser;
+use std::time::Duration;
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let mut url = None;
     let cli = args::Args::parse();
 
-    if !cli.category.is_empty() {
-        url = Some(format!("https://nos.nl/nieuws/{}", cli.category))
+    if cli.print_profile {
+        print!("{}", profile::DEFAULT_PROFILE);
+        return Ok(());
     }
 
-    let mut app = App::new(url);
+    let profile = SiteProfile::load(cli.profile.as_deref())?;
+    let mut sources = Sources::new(cli.source, profile);
+    if cli.archive {
+        sources.set_archive(Archive::open()?);
+    }
+    if cli.list_categories {
+        for (name, categories) in sources.categories() {
+            println!("{}: {}", name, categories.join(", "));
+        }
+        return Ok(());
+    }
+
+    let categories: Vec<String> = cli
+        .category
+        .split(',')
+        .map(str::trim)
+        .filter(|category| !category.is_empty())
+        .map(String::from)
+        .collect();
+    let watchlist = Watchlist::load(cli.watchlist.as_deref())?;
+    let hooks = Hooks::load(cli.hooks.as_deref())?;
+
+    match cli.command {
+        Some(Command::Doctor) => {
+            let healthy = doctor::run(&sources);
+            std::process::exit(if healthy { 0 } else { 1 });
+        }
+        Some(Command::Digest {
+            path,
+            count,
+            format,
+        }) => {
+            let format = format
+                .or_else(|| ExportFormat::from_path(&path))
+                .ok_or("Can't tell the format from the path, use --format")?;
+            digest::run(&sources, &categories, count as usize, &path, format)?;
+            return Ok(());
+        }
+        Some(Command::Watch {
+            interval,
+            notify_command,
+            watchlist_only,
+        }) => {
+            let options = daemon::Options {
+                interval: Duration::from_secs(interval),
+                command: notify_command,
+                watchlist_only,
+            };
+            daemon::run(&sources, &categories, &watchlist, &hooks, &options)?;
+            return Ok(());
+        }
+        Some(Command::Search {
+            keywords,
+            since,
+            until,
+            limit,
+        }) => {
+            let query = Query {
+                keywords,
+                since,
+                until,
+            };
+            for article in Archive::open()?.search(&query, limit)? {
+                let time = match article.published {
+                    Some(published) => published.format("%Y-%m-%d %H:%M").to_string(),
+                    None => article.datetime.clone(),
+                };
+                println!("{} [{}] {}", time, article.category, article.title);
+                println!("    {}", article.href);
+            }
+            return Ok(());
+        }
+        None => (),
+    }
+
+    if let Some(path) = cli.export {
+        let format = cli
+            .export_format
+            .or_else(|| ExportFormat::from_path(&path))
+            .ok_or("Can't tell the export format from the path, use --export-format")?;
+        let document = export::fetch_document(&sources, &categories, cli.random)?;
+        let title = document.article.title.clone();
+        export::write(&path, format, &title, &[document])?;
+        println!("Exported \"{}\" to {}", title, path.display());
+        return Ok(());
+    }
+
+    let refresh_interval = cli.refresh_interval.map(Duration::from_secs);
+
+    let mut app = App::new(
+        categories,
+        sources,
+        refresh_interval,
+        cli.time_format,
+        cli.images,
+        cli.save_bookmark_bodies,
+        cli.player,
+    )?;
 
+    app.set_watchlist(watchlist);
+    app.set_hooks(hooks);
+    app.set_layout(cli.layout, cli.split_ratio);
     if cli.random {
         app.enter_random_article();
     }
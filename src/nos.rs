@@ -0,0 +1,149 @@
+use scraper::Html;
+
+use crate::{
+    profile::{self, NosProfile},
+    rss,
+    scrape::{self, Block, BodySelectors, Page},
+    source::Source,
+};
+
+/// Where NOS article listings are read from.
+#[derive(Clone, Copy, Debug, PartialEq, clap::ValueEnum)]
+pub enum Backend {
+    /// The RSS feeds of nos.nl
+    Rss,
+    /// The category pages of nos.nl
+    Html,
+}
+
+impl Backend {
+    fn other(self) -> Self {
+        match self {
+            Backend::Rss => Backend::Html,
+            Backend::Html => Backend::Rss,
+        }
+    }
+}
+
+/// The news section of nos.nl.
+pub struct Nos {
+    backend: Backend,
+    profile: NosProfile,
+}
+
+impl Nos {
+    pub fn new(backend: Backend, profile: NosProfile) -> Self {
+        Nos { backend, profile }
+    }
+
+    /// URL of the RSS feed for a category, e.g. `https://feeds.nos.nl/nosnieuwsbinnenland`.
+    fn rss_url(&self, category: &str) -> String {
+        match self.profile.feeds.get(category) {
+            Some(url) => url.clone(),
+            None => profile::fill_pattern(&self.profile.feed_url, &category.replace('-', "")),
+        }
+    }
+
+    fn get_listing_from(
+        &self,
+        category: &str,
+        backend: Backend,
+    ) -> Result<Page, Box<dyn std::error::Error>> {
+        match backend {
+            Backend::Rss => rss::get_items(&self.rss_url(category), category),
+            Backend::Html => {
+                scrape::get_items(self.category_url(category), category, &self.profile)
+            }
+        }
+    }
+}
+
+impl Source for Nos {
+    fn name(&self) -> &'static str {
+        "nos"
+    }
+
+    fn categories(&self) -> &[&'static str] {
+        &[
+            "laatste",
+            "binnenland",
+            "buitenland",
+            "regio",
+            "politiek",
+            "economie",
+            "koningshuis",
+            "tech",
+            "cultuur-en-media",
+            "opmerkelijk",
+        ]
+    }
+
+    fn category_url(&self, category: &str) -> String {
+        profile::fill_pattern(&self.profile.category_url, category)
+    }
+
+    fn listing_selectors(&self) -> Vec<String> {
+        let article = &self.profile.article;
+        vec![
+            article.clone(),
+            scrape::nested_selector(article, &self.profile.title),
+            scrape::nested_selector(article, &self.profile.link),
+            scrape::nested_selector(article, &self.profile.datetime),
+        ]
+    }
+
+    fn article_selectors(&self) -> Vec<String> {
+        vec![
+            self.profile.text.clone(),
+            self.profile.image.clone(),
+            self.profile.media.clone(),
+        ]
+    }
+
+    /// Fetches the listing from the configured backend, falling back to the
+    /// other one when it fails or has no articles.
+    fn get_listing(&self, category: &str) -> Result<Page, Box<dyn std::error::Error>> {
+        let page = self.get_listing_from(category, self.backend);
+        if matches!(&page, Ok(page) if !page.articles.is_empty()) {
+            return page;
+        }
+
+        match self.get_listing_from(category, self.backend.other()) {
+            Ok(fallback) if !fallback.articles.is_empty() => Ok(fallback),
+            _ => page,
+        }
+    }
+
+    fn get_next_page(&self, url: &str, category: &str) -> Result<Page, Box<dyn std::error::Error>> {
+        scrape::get_items(url.to_string(), category, &self.profile)
+    }
+
+    fn get_article(&self, url: &str) -> Result<Vec<Block>, Box<dyn std::error::Error>> {
+        scrape::get_article(url, &self.profile)
+    }
+
+    fn parse_listing(
+        &self,
+        document: &Html,
+        url: &str,
+        category: &str,
+    ) -> Result<Page, Box<dyn std::error::Error>> {
+        scrape::parse_items(document, url, category, &self.profile)
+    }
+
+    fn parse_article(
+        &self,
+        document: &Html,
+        url: &str,
+    ) -> Result<Vec<Block>, Box<dyn std::error::Error>> {
+        scrape::parse_blocks(document, url, &BodySelectors::from(&self.profile))
+    }
+
+    fn feed_url(&self, category: &str) -> Option<String> {
+        Some(self.rss_url(category))
+    }
+
+    fn reads_feeds(&self) -> bool {
+        self.backend == Backend::Rss
+    }
+}
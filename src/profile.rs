@@ -0,0 +1,144 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use serde::Deserialize;
+
+use crate::store;
+
+/// The built-in site profile, matching nos.nl at the time of release.
+pub const DEFAULT_PROFILE: &str = include_str!("../assets/profile.toml");
+
+/// Version of the profile format this build understands.
+const PROFILE_VERSION: u32 = 1;
+
+/// Where articles are fetched from and the selectors used to read them, so a
+/// scraper broken by a change to the site can be fixed without a new release.
+#[derive(Clone, Deserialize)]
+pub struct SiteProfile {
+    pub version: u32,
+    pub nos: NosProfile,
+    pub sport: SportProfile,
+    pub liveblog: LiveblogProfile,
+}
+
+#[derive(Clone, Deserialize)]
+pub struct NosProfile {
+    pub category_url: String,
+    pub feed_url: String,
+    pub feeds: HashMap<String, String>,
+    pub not_found: String,
+    pub article: String,
+    pub title: String,
+    pub link: String,
+    pub datetime: String,
+    pub text: String,
+    pub image: String,
+    pub caption: String,
+    pub media: String,
+}
+
+#[derive(Clone, Deserialize)]
+pub struct SportProfile {
+    pub category_url: String,
+    pub pages: HashMap<String, String>,
+    pub not_found: String,
+    pub link: String,
+    pub title: String,
+    pub datetime: String,
+    pub scoreboard: String,
+    pub text: String,
+    pub fallback_text: String,
+    pub image: String,
+    pub caption: String,
+    pub media: String,
+}
+
+#[derive(Clone, Deserialize)]
+pub struct LiveblogProfile {
+    pub url_pattern: String,
+    pub entry: String,
+    pub datetime: String,
+    pub title: String,
+    pub text: String,
+}
+
+impl Default for SiteProfile {
+    fn default() -> Self {
+        toml::from_str(DEFAULT_PROFILE).expect("The built-in profile is invalid")
+    }
+}
+
+impl SiteProfile {
+    /// Loads the profile at `path`, or from `profile.toml` in the config
+    /// directory when no path is given. Settings missing from the file keep
+    /// their built-in value, without a file the built-in profile is used.
+    pub fn load(path: Option<&Path>) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut profile: toml::Table = toml::from_str(DEFAULT_PROFILE)?;
+
+        let path = match path {
+            Some(path) => Some(path.to_path_buf()),
+            None => default_path().filter(|path| path.exists()),
+        };
+        if let Some(path) = path {
+            let contents = fs::read_to_string(&path)
+                .map_err(|e| format!("Couldn't read {}: {}", path.display(), e))?;
+            let overrides: toml::Table = toml::from_str(&contents)
+                .map_err(|e| format!("Invalid profile {}: {}", path.display(), e))?;
+            merge(&mut profile, overrides);
+        }
+
+        let profile: SiteProfile = toml::Value::Table(profile).try_into()?;
+        if profile.version != PROFILE_VERSION {
+            return Err(format!(
+                "Unsupported profile version {}, this version of nos-cli reads version {}",
+                profile.version, PROFILE_VERSION
+            )
+            .into());
+        }
+        Ok(profile)
+    }
+}
+
+/// `~/.config/nos-cli/profile.toml`
+fn default_path() -> Option<PathBuf> {
+    store::config_dir().map(|dir| dir.join("profile.toml"))
+}
+
+/// Fills in a `{}` placeholder in a URL pattern.
+pub fn fill_pattern(pattern: &str, category: &str) -> String {
+    pattern.replace("{}", category)
+}
+
+/// Recursively overwrites the values in `base` with those in `overrides`.
+fn merge(base: &mut toml::Table, overrides: toml::Table) {
+    for (key, value) in overrides {
+        match (base.get_mut(&key), value) {
+            (Some(toml::Value::Table(base_table)), toml::Value::Table(table)) => {
+                merge(base_table, table)
+            }
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merge_overrides() {
+        let mut profile: toml::Table = toml::from_str(DEFAULT_PROFILE).unwrap();
+        let overrides = toml::from_str("[nos]\narticle = \"ul > li\"").unwrap();
+        merge(&mut profile, overrides);
+
+        let profile: SiteProfile = toml::Value::Table(profile).try_into().unwrap();
+        assert_eq!(profile.nos.article, "ul > li");
+        assert_eq!(profile.nos.title, "h2");
+        assert_eq!(profile.sport.pages["sport"], "https://nos.nl/sport/laatste");
+    }
+}
@@ -1,38 +1,200 @@
 use crate::termion::raw::IntoRawMode;
-use std::io::{Read, StdinLock, StdoutLock, Write, stdin, stdout};
-use termion::{color, cursor, event::Key, input::TermRead, raw::RawTerminal};
+use std::{
+    io::{StdoutLock, Write, stdin, stdout},
+    sync::mpsc::{self, Receiver},
+    thread,
+    time::Duration,
+};
+use termion::{
+    color, cursor,
+    event::{Event, Key},
+    input::{MouseTerminal, TermRead},
+    raw::RawTerminal,
+    style,
+};
+
+/// Badge shown in front of articles that appeared since the list was first loaded.
+pub const NEW_BADGE: &str = "NIEUW";
+
+/// Removes every image drawn with the kitty graphics protocol.
+const KITTY_DELETE_IMAGES: &str = "\x1b_Ga=d,q=2\x1b\\";
+
+/// Narrowest tab label, with more tabs than fit the tab bar scrolls instead.
+const MIN_TAB_WIDTH: usize = 16;
+
+/// Extra information on how to draw a title, besides whether it is selected.
+#[derive(Clone, Default)]
+pub struct TitleStyle {
+    pub new: bool,
+    pub read: bool,
+    pub bookmarked: bool,
+    /// Matches the watchlist
+    pub watched: bool,
+    /// A day separator rather than an article
+    pub separator: bool,
+}
 
 pub struct Renderer<'a> {
-    stdout: RawTerminal<StdoutLock<'a>>, // TODO: look into AlternateScreen
-    stdin: StdinLock<'a>,
+    stdout: MouseTerminal<RawTerminal<StdoutLock<'a>>>, // TODO: look into AlternateScreen
+    events: Receiver<Event>,
     term_height: usize,
+    term_width: usize,
+    /// Rows at the top taken by the tab bar
+    top: usize,
+    /// Kitty images stay on screen until they're deleted, unlike text
+    kitty_images: bool,
 }
 
 impl<'a> Renderer<'a> {
     pub fn new() -> Self {
         let stdout = stdout();
-        let stdout = stdout.lock().into_raw_mode().unwrap();
-        let stdin = stdin();
-        let stdin = stdin.lock();
+        let stdout = MouseTerminal::from(stdout.lock().into_raw_mode().unwrap());
+
+        // Read events on a separate thread so we can wait for them with a timeout
+        let (sender, events) = mpsc::channel();
+        thread::spawn(move || {
+            for event in stdin().events().map_while(Result::ok) {
+                if sender.send(event).is_err() {
+                    break;
+                }
+            }
+        });
 
-        let (_, term_height) = termion::terminal_size().unwrap();
+        let (term_width, term_height) = termion::terminal_size().unwrap();
         let term_height = term_height as usize - 1;
+        let term_width = term_width as usize;
 
         Renderer {
             stdout,
-            stdin,
+            events,
             term_height,
+            term_width,
+            top: 0,
+            kitty_images: false,
         }
     }
 
-    pub fn print_titles(&mut self, titles: &[String], selected_row: usize) {
+    /// Moves the list and the article down to make room for the tab bar.
+    pub fn set_top(&mut self, rows: usize) {
+        self.top = rows;
+    }
+
+    /// Draws a label for every tab on the top row, highlighting the current
+    /// one. It is dimmed while the list has focus, like the selected title.
+    /// When the labels don't fit, the bar scrolls so the current one stays
+    /// visible.
+    pub fn print_tab_bar(&mut self, titles: &[String], current: usize, focused: bool) {
+        let label_width = (self.term_width / titles.len().max(1))
+            .max(MIN_TAB_WIDTH)
+            .min(self.term_width);
+        let visible = (self.term_width / label_width.max(1)).max(1);
+        let first = (current + 1).saturating_sub(visible);
+        write!(
+            self.stdout,
+            "{}{}",
+            termion::cursor::Goto(1, 1),
+            termion::clear::CurrentLine
+        )
+        .unwrap();
+        for (column, (i, title)) in titles
+            .iter()
+            .enumerate()
+            .skip(first)
+            .take(visible)
+            .enumerate()
+        {
+            let label: String = format!(" {}: {} ", i + 1, title)
+                .chars()
+                .take(label_width.saturating_sub(1))
+                .collect();
+            if i == current && focused {
+                write!(
+                    self.stdout,
+                    "{}{}{}{}{}",
+                    color::Bg(color::White),
+                    color::Fg(color::Black),
+                    label,
+                    color::Fg(color::Reset),
+                    color::Bg(color::Reset),
+                )
+                .unwrap();
+            } else if i == current {
+                write!(
+                    self.stdout,
+                    "{}{}{}",
+                    color::Bg(color::LightBlack),
+                    label,
+                    color::Bg(color::Reset),
+                )
+                .unwrap();
+            } else {
+                write!(self.stdout, "{}{}{}", style::Faint, label, style::NoFaint).unwrap();
+            }
+            write!(
+                self.stdout,
+                "{}",
+                termion::cursor::Goto(((column + 1) * label_width + 1) as u16, 1)
+            )
+            .unwrap();
+        }
+        self.flush();
+    }
+
+    /// Draws the titles. The selected one is dimmed when the list doesn't have
+    /// focus, because the article next to it has.
+    pub fn print_titles(
+        &mut self,
+        titles: &[String],
+        styles: &[TitleStyle],
+        selected_row: usize,
+        focused: bool,
+    ) {
         self.clear_main();
         for (i, title) in titles.iter().enumerate() {
-            if i == selected_row {
+            write!(
+                self.stdout,
+                "{}",
+                termion::cursor::Goto(1, (self.top + i) as u16 + 1)
+            )
+            .unwrap();
+            let style = styles.get(i).cloned().unwrap_or_default();
+            if style.separator {
+                write!(
+                    self.stdout,
+                    "{}{}{}{}",
+                    style::Bold,
+                    style::Underline,
+                    title,
+                    style::Reset
+                )
+                .unwrap();
+                continue;
+            }
+            if style.new {
+                write!(
+                    self.stdout,
+                    "{}{}{}{}{} ",
+                    color::Bg(color::Yellow),
+                    color::Fg(color::Black),
+                    NEW_BADGE,
+                    color::Fg(color::Reset),
+                    color::Bg(color::Reset),
+                )
+                .unwrap();
+            }
+            if i == selected_row && !focused {
+                write!(
+                    self.stdout,
+                    "{}{}{}",
+                    color::Bg(color::LightBlack),
+                    title,
+                    color::Bg(color::Reset),
+                )
+                .unwrap();
+            } else if i == selected_row {
                 write!(
                     self.stdout,
-                    "{}{}{}{}{}{}",
-                    termion::cursor::Goto(1, i as u16 + 1),
+                    "{}{}{}{}{}",
                     color::Bg(color::White),
                     color::Fg(color::Black),
                     title,
@@ -41,11 +203,20 @@ impl<'a> Renderer<'a> {
                 )
                 .unwrap();
             } else {
+                if style.read {
+                    write!(self.stdout, "{}", style::Faint).unwrap();
+                }
+                if style.watched {
+                    write!(self.stdout, "{}{}", style::Bold, color::Fg(color::Magenta)).unwrap();
+                } else if style.bookmarked {
+                    write!(self.stdout, "{}", color::Fg(color::Cyan)).unwrap();
+                }
                 write!(
                     self.stdout,
-                    "{}{}",
-                    termion::cursor::Goto(1, i as u16 + 1),
-                    title
+                    "{}{}{}",
+                    title,
+                    color::Fg(color::Reset),
+                    style::NoFaint
                 )
                 .unwrap();
             }
@@ -53,18 +224,85 @@ impl<'a> Renderer<'a> {
         self.flush();
     }
 
-    pub fn print_article(&mut self, subset_article: &[String]) {
+    /// Draws the article to the right of the list, `list_width` columns from
+    /// the left edge, over what `print_titles` drew.
+    pub fn print_preview(
+        &mut self,
+        list_width: usize,
+        subset_article: &[String],
+        images: &[(usize, &str)],
+    ) {
+        let column = list_width as u16 + 1;
+        for row in self.top + 1..=self.term_height {
+            write!(
+                self.stdout,
+                "{}{}│{}",
+                termion::cursor::Goto(column, row as u16),
+                style::Faint,
+                style::NoFaint
+            )
+            .unwrap();
+        }
+
+        for (i, line) in subset_article.iter().enumerate() {
+            // Lines start with a line break, which would go back to the left edge
+            let (line, row) = match line.strip_prefix("\r\n") {
+                Some(line) => (line, self.top + i + 2),
+                None => (line.as_str(), self.top + i + 1),
+            };
+            if row > self.term_height {
+                break;
+            }
+            write!(
+                self.stdout,
+                "{}{}",
+                termion::cursor::Goto(column + 1, row as u16),
+                line
+            )
+            .unwrap();
+        }
+
+        for &(line, data) in images {
+            write!(
+                self.stdout,
+                "{}{}",
+                termion::cursor::Goto(column + 1, (self.top + line + 2) as u16),
+                data
+            )
+            .unwrap();
+            self.kitty_images |= data.starts_with("\x1b_G");
+        }
+        self.flush();
+    }
+
+    /// Draws the article text, and the images on top of it. `images` holds the
+    /// line in `subset_article` each image starts at and its escape sequence.
+    pub fn print_article(&mut self, subset_article: &[String], images: &[(usize, &str)]) {
         self.clear_main();
 
         for (i, line) in subset_article.iter().enumerate() {
             write!(
                 self.stdout,
                 "{}{}",
-                termion::cursor::Goto(1, (i + 1) as u16),
+                termion::cursor::Goto(1, (self.top + i + 1) as u16),
                 line
             )
             .unwrap();
         }
+
+        for &(line, data) in images {
+            // Lines start with a line break, so their text ends up one row lower
+            let row = self.top + line + 2;
+            write!(
+                self.stdout,
+                "{}{}{}",
+                termion::cursor::Goto(1, row as u16),
+                termion::clear::CurrentLine,
+                data
+            )
+            .unwrap();
+            self.kitty_images |= data.starts_with("\x1b_G");
+        }
         self.flush();
     }
 
@@ -94,11 +332,58 @@ impl<'a> Renderer<'a> {
         self.flush();
     }
 
+    /// Shows the number of unread watchlist hits at the right of the status bar.
+    pub fn write_watch_alerts(&mut self, count: usize) {
+        let alert = format!("{} watch", count);
+        let column = self.term_width.saturating_sub(alert.len() + 1) + 1;
+        write!(
+            self.stdout,
+            "{}{}{}{}{}",
+            termion::cursor::Goto(column as u16, self.term_height as u16 + 1),
+            color::Fg(color::Magenta),
+            alert,
+            color::Fg(color::Reset),
+            termion::cursor::Goto(1, self.term_height as u16 + 1),
+        )
+        .unwrap();
+        self.flush();
+    }
+
+    /// Writes the keys of an unfinished key sequence to the right of the status bar.
+    pub fn write_pending_keys(&mut self, keys: &str) {
+        let column = self.term_width.saturating_sub(keys.len() + 1) + 1;
+        write!(
+            self.stdout,
+            "{}{}{}{}",
+            termion::cursor::Goto(1, self.term_height as u16 + 1),
+            termion::clear::CurrentLine,
+            termion::cursor::Goto(column as u16, self.term_height as u16 + 1),
+            keys
+        )
+        .unwrap();
+        self.flush();
+    }
+
+    /// Waits for the next keystroke, ignoring mouse events.
     pub fn get_keystroke(&mut self) -> Key {
-        self.stdin.by_ref().keys().next().unwrap().unwrap()
+        loop {
+            if let Event::Key(key) = self.get_event() {
+                return key;
+            }
+        }
+    }
+
+    pub fn get_event(&mut self) -> Event {
+        self.events.recv().unwrap()
+    }
+
+    /// Like `get_event`, but gives up after `timeout`.
+    pub fn get_event_timeout(&mut self, timeout: Duration) -> Option<Event> {
+        self.events.recv_timeout(timeout).ok()
     }
 
     pub fn clear_all(&mut self) {
+        self.clear_images();
         write!(self.stdout, "{}", termion::clear::All).unwrap();
     }
 
@@ -112,7 +397,15 @@ impl<'a> Renderer<'a> {
         .unwrap();
     }
 
+    fn clear_images(&mut self) {
+        if self.kitty_images {
+            write!(self.stdout, "{}", KITTY_DELETE_IMAGES).unwrap();
+            self.kitty_images = false;
+        }
+    }
+
     fn clear_main(&mut self) {
+        self.clear_images();
         for i in 0..=self.term_height {
             write!(
                 self.stdout,
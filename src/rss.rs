@@ -0,0 +1,45 @@
+use chrono::Local;
+
+use crate::{
+    scrape::{Article, Page},
+    util,
+};
+
+/// Reads the articles from an RSS or Atom feed. Feeds have no further pages.
+pub fn get_items(url: &str, category: &str) -> Result<Page, Box<dyn std::error::Error>> {
+    let body = reqwest::blocking::get(url)?.error_for_status()?.bytes()?;
+    parse_items(&body, category)
+}
+
+pub fn parse_items(body: &[u8], category: &str) -> Result<Page, Box<dyn std::error::Error>> {
+    let feed = feed_rs::parser::parse(body)?;
+
+    let mut articles = Vec::new();
+
+    for entry in feed.entries {
+        let title = entry.title.map(|t| t.content).unwrap_or_default();
+        let href = entry
+            .links
+            .first()
+            .map(|link| link.href.clone())
+            .unwrap_or_default();
+        let published = entry.published.or(entry.updated).map(|p| p.fixed_offset());
+        let datetime = published
+            .map(|p| util::format_absolute(p, Local::now()))
+            .unwrap_or_default();
+
+        articles.push(Article {
+            title,
+            href,
+            datetime,
+            published,
+            category: category.to_string(),
+            source: String::new(),
+        });
+    }
+
+    Ok(Page {
+        articles,
+        next_page: None,
+    })
+}
@@ -1,32 +1,163 @@
-use crate::util;
-use reqwest;
-use scraper::{Html, Selector};
+use crate::{
+    profile::{NosProfile, SportProfile},
+    util,
+};
+use std::fmt;
 
-#[derive(Clone)]
+use chrono::{DateTime, FixedOffset};
+use scraper::{ElementRef, Html, Selector};
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Article {
     pub title: String,
     pub href: String,
     pub datetime: String,
+    /// Publication time, parsed from the `datetime` attribute
+    #[serde(default)]
+    pub published: Option<DateTime<FixedOffset>>,
+    pub category: String,
+    /// Name of the `Source` the article came from
+    #[serde(default)]
+    pub source: String,
 }
 
-pub fn get_items(url: String) -> Result<Vec<Article>, Box<dyn std::error::Error>> {
-    let body = reqwest::blocking::get(url)?.text()?;
-    let document = Html::parse_document(&body);
+/// A piece of an article's body.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Block {
+    /// A paragraph
+    Text(String),
+    /// A subheading. A struct variant, so it can be told apart from `Text` when
+    /// reading bookmarks back, older ones stored subheadings as text.
+    Heading {
+        heading: String,
+    },
+    /// A paragraph with links in it. The links are kept next to the plain
+    /// text, so everything that only shows text can use `text` as it is.
+    Linked {
+        text: String,
+        links: Vec<Link>,
+    },
+    Image(Image),
+    Media(Media),
+}
 
-    let potential_error_selector = Selector::parse("h1")?;
-    let potential_error_msg = document
-        .select(&potential_error_selector)
-        .next()
-        .map(util::element_to_text)
-        .unwrap_or_default();
-    if potential_error_msg == "De pagina kan helaas niet worden gevonden" {
-        panic!("The given category is not a valid option!")
+/// A link in a paragraph, in the order they appear in.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Link {
+    pub text: String,
+    pub href: String,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Image {
+    pub url: String,
+    pub caption: String,
+}
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum MediaKind {
+    Video,
+    Audio,
+}
+
+/// An embedded video or audio fragment, played with an external player.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Media {
+    pub kind: MediaKind,
+    pub title: String,
+    /// Length formatted as e.g. "1:30"
+    pub duration: Option<String>,
+    pub url: String,
+}
+
+impl fmt::Display for Media {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let kind = match self.kind {
+            MediaKind::Video => "Video",
+            MediaKind::Audio => "Audio",
+        };
+        write!(f, "{}: {}", kind, self.title)?;
+        if let Some(duration) = &self.duration {
+            write!(f, " ({})", duration)?;
+        }
+        Ok(())
+    }
+}
+
+/// Selectors for the parts of an article's body.
+pub struct BodySelectors<'a> {
+    pub text: &'a str,
+    pub image: &'a str,
+    /// Caption of an image, within the `image` element
+    pub caption: &'a str,
+    pub media: &'a str,
+}
+
+impl<'a> From<&'a NosProfile> for BodySelectors<'a> {
+    fn from(profile: &'a NosProfile) -> Self {
+        BodySelectors {
+            text: &profile.text,
+            image: &profile.image,
+            caption: &profile.caption,
+            media: &profile.media,
+        }
     }
+}
+
+impl<'a> From<&'a SportProfile> for BodySelectors<'a> {
+    fn from(profile: &'a SportProfile) -> Self {
+        BodySelectors {
+            text: &profile.text,
+            image: &profile.image,
+            caption: &profile.caption,
+            media: &profile.media,
+        }
+    }
+}
+
+impl fmt::Display for Block {
+    /// The text of the block, images are shown as their caption.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Block::Text(text) | Block::Linked { text, .. } => write!(f, "{}", text),
+            Block::Heading { heading } => write!(f, "{}", heading),
+            Block::Image(image) => write!(f, "[Afbeelding: {}]", image.caption),
+            Block::Media(media) => write!(f, "[{}]", media),
+        }
+    }
+}
+
+/// One page of a category listing.
+pub struct Page {
+    pub articles: Vec<Article>,
+    /// Link to the page with older articles, if there is one
+    pub next_page: Option<String>,
+}
+
+/// Fetches a listing page whose articles are matched by `profile.article`.
+pub fn get_items(
+    url: String,
+    category: &str,
+    profile: &NosProfile,
+) -> Result<Page, Box<dyn std::error::Error>> {
+    let body = reqwest::blocking::get(&url)?.text()?;
+    parse_items(&Html::parse_document(&body), &url, category, profile)
+}
+
+pub fn parse_items(
+    document: &Html,
+    url: &str,
+    category: &str,
+    profile: &NosProfile,
+) -> Result<Page, Box<dyn std::error::Error>> {
+    check_category_exists(document, category, &profile.not_found)?;
 
-    let article_selector = Selector::parse("section > ul > li")?;
-    let title_selector = Selector::parse("h2")?;
-    let link_selector = Selector::parse("a")?;
-    let datetime_selector = Selector::parse("span > time")?;
+    let article_selector = selector(&profile.article)?;
+    let title_selector = selector(&profile.title)?;
+    let link_selector = selector(&profile.link)?;
+    let datetime_selector = selector(&profile.datetime)?;
 
     let mut articles = Vec::new();
 
@@ -41,48 +172,370 @@ pub fn get_items(url: String) -> Result<Vec<Article>, Box<dyn std::error::Error>
             .select(&link_selector)
             .next()
             .and_then(|e| e.value().attr("href"))
-            .map(|href| format!("https://nos.nl{}", href))
+            .map(|href| absolute_url(href, url))
             .unwrap_or_default();
 
-        let datetime = article
-            .select(&datetime_selector)
-            .next()
-            .map(util::element_to_text)
-            .unwrap_or_default();
+        let time_element = article.select(&datetime_selector).next();
+        let datetime = time_element.map(util::element_to_text).unwrap_or_default();
+        let published = time_element
+            .and_then(|e| e.value().attr("datetime"))
+            .and_then(util::parse_timestamp);
 
         articles.push(Article {
             title,
             href,
             datetime,
+            published,
+            category: category.to_string(),
+            source: String::new(),
         });
     }
-    Ok(articles)
+
+    let next_page = find_next_page(document, url)?;
+
+    Ok(Page {
+        articles,
+        next_page,
+    })
+}
+
+/// Selector for `child` elements within `parent` elements, e.g. for
+/// checking how many articles in a listing have a title.
+pub fn nested_selector(parent: &str, child: &str) -> String {
+    child
+        .split(',')
+        .map(|child| format!("{} {}", parent, child.trim()))
+        .collect::<Vec<_>>()
+        .join(", ")
 }
 
-pub fn get_article(url: &str) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+/// Parses a selector from the site profile.
+pub fn selector(selector: &str) -> Result<Selector, Box<dyn std::error::Error>> {
+    Selector::parse(selector).map_err(|e| format!("Invalid selector `{}`: {}", selector, e).into())
+}
+
+/// Fails when nos.nl answered with its "page not found" page, recognised by
+/// its `not_found` heading.
+pub fn check_category_exists(
+    document: &Html,
+    category: &str,
+    not_found: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let potential_error_selector = Selector::parse("h1")?;
+    let potential_error_msg = document
+        .select(&potential_error_selector)
+        .next()
+        .map(util::element_to_text)
+        .unwrap_or_default();
+    if potential_error_msg == not_found {
+        return Err(format!("{} is not a valid category!", category).into());
+    }
+    Ok(())
+}
+
+/// Looks for the `rel="next"` link to older articles in a listing.
+pub fn find_next_page(
+    document: &Html,
+    url: &str,
+) -> Result<Option<String>, Box<dyn std::error::Error>> {
+    let next_selector = Selector::parse("link[rel=next], a[rel=next]")?;
+
+    let href = document
+        .select(&next_selector)
+        .next()
+        .and_then(|link| link.value().attr("href"));
+
+    Ok(href.map(|href| absolute_url(href, url)))
+}
+
+/// Resolves a link found on `page_url`.
+pub fn absolute_url(href: &str, page_url: &str) -> String {
+    if href.starts_with("http") {
+        href.to_string()
+    } else if href.starts_with('?') {
+        let base = page_url.split('?').next().unwrap_or_default();
+        format!("{}{}", base, href)
+    } else {
+        // Everything up to the first slash after `https://`
+        let origin_end = page_url
+            .match_indices('/')
+            .nth(2)
+            .map_or(page_url.len(), |(i, _)| i);
+        format!("{}{}", &page_url[..origin_end], href)
+    }
+}
+
+pub fn get_article(
+    url: &str,
+    profile: &NosProfile,
+) -> Result<Vec<Block>, Box<dyn std::error::Error>> {
     let body = reqwest::blocking::get(url)?.text()?;
     let document = Html::parse_document(&body);
 
-    let text_selector = Selector::parse("main > div > p, main > div > h2")?;
+    parse_blocks(&document, url, &BodySelectors::from(profile))
+}
 
-    let mut all_text = Vec::new();
+/// Reads the text, images and media of an article in the order they appear
+/// in. Media that is only described in the page's linked data comes first.
+pub fn parse_blocks(
+    document: &Html,
+    url: &str,
+    selectors: &BodySelectors,
+) -> Result<Vec<Block>, Box<dyn std::error::Error>> {
+    let block_selector = selector(&format!(
+        "{}, {}, {}",
+        selectors.text, selectors.image, selectors.media
+    ))?;
+    let image_selector = selector(selectors.image)?;
+    let caption_selector = selector(selectors.caption)?;
+    let media_selector = selector(selectors.media)?;
+    let link_selector = selector("a[href]")?;
 
-    for element in document.select(&text_selector) {
-        let element_text = util::element_to_text(element);
-        all_text.push(element_text);
+    let mut blocks = Vec::new();
+
+    for element in document.select(&block_selector) {
+        if media_selector.matches(&element) {
+            if let Some(media) = element_media(element, url) {
+                blocks.push(Block::Media(media));
+            }
+            continue;
+        }
+        if !image_selector.matches(&element) {
+            let text = util::element_to_text(element);
+            match element.value().name() {
+                "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                    blocks.push(Block::Heading { heading: text })
+                }
+                _ => {
+                    let links = element_links(element, &link_selector, url);
+                    if links.is_empty() {
+                        blocks.push(Block::Text(text));
+                    } else {
+                        blocks.push(Block::Linked { text, links });
+                    }
+                }
+            }
+            continue;
+        }
+        let Some(image_url) = image_url(element) else {
+            continue;
+        };
+        let caption = element
+            .select(&caption_selector)
+            .next()
+            .map(util::element_to_text)
+            .unwrap_or_default();
+        blocks.push(Block::Image(Image {
+            url: absolute_url(&image_url, url),
+            caption: caption.trim().to_string(),
+        }));
     }
 
-    Ok(all_text)
+    let embedded: Vec<Media> = linked_data_media(document)?
+        .into_iter()
+        .filter(|media| {
+            !blocks
+                .iter()
+                .any(|block| matches!(block, Block::Media(m) if m.url == media.url))
+        })
+        .collect();
+    blocks.splice(0..0, embedded.into_iter().map(Block::Media));
+
+    Ok(blocks)
+}
+
+/// A `<video>` or `<audio>` element with a source.
+fn element_media(element: ElementRef, page_url: &str) -> Option<Media> {
+    let kind = match element.value().name() {
+        "audio" => MediaKind::Audio,
+        _ => MediaKind::Video,
+    };
+    let source_selector = Selector::parse("source[src]").ok()?;
+    let src = element.value().attr("src").or_else(|| {
+        element
+            .select(&source_selector)
+            .next()
+            .and_then(|source| source.value().attr("src"))
+    })?;
+
+    let attrs = element.value();
+    let title = ["title", "aria-label", "data-title"]
+        .iter()
+        .find_map(|name| attrs.attr(name))
+        .unwrap_or_default();
+    let duration = attrs
+        .attr("data-duration")
+        .and_then(|seconds| seconds.parse::<u64>().ok())
+        .map(util::format_duration);
+
+    Some(Media {
+        kind,
+        title: title.to_string(),
+        duration,
+        url: absolute_url(src, page_url),
+    })
+}
+
+/// Videos and audio described as `VideoObject` or `AudioObject` in the JSON-LD
+/// of the page, which is how players that are loaded by scripts show up.
+fn linked_data_media(document: &Html) -> Result<Vec<Media>, Box<dyn std::error::Error>> {
+    let script_selector = selector(r#"script[type="application/ld+json"]"#)?;
+    let mut media = Vec::new();
+
+    for script in document.select(&script_selector) {
+        let json = script.text().collect::<String>();
+        if let Ok(value) = serde_json::from_str::<serde_json::Value>(&json) {
+            collect_linked_data_media(&value, &mut media);
+        }
+    }
+    Ok(media)
+}
+
+fn collect_linked_data_media(value: &serde_json::Value, media: &mut Vec<Media>) {
+    match value {
+        serde_json::Value::Array(values) => {
+            for value in values {
+                collect_linked_data_media(value, media);
+            }
+        }
+        serde_json::Value::Object(object) => {
+            if let Some(graph) = object.get("@graph") {
+                collect_linked_data_media(graph, media);
+            }
+            let kind = match object.get("@type").and_then(|t| t.as_str()) {
+                Some("VideoObject") => MediaKind::Video,
+                Some("AudioObject") => MediaKind::Audio,
+                _ => return,
+            };
+            let string = |key: &str| object.get(key).and_then(|v| v.as_str());
+            let Some(url) = string("contentUrl").or_else(|| string("embedUrl")) else {
+                return;
+            };
+            media.push(Media {
+                kind,
+                title: string("name").unwrap_or_default().to_string(),
+                duration: string("duration")
+                    .and_then(util::parse_iso_duration)
+                    .map(util::format_duration),
+                url: url.to_string(),
+            });
+        }
+        _ => (),
+    }
+}
+
+/// Source of the first `<img>` in `element`, which may be lazily loaded.
+fn image_url(element: ElementRef) -> Option<String> {
+    let img_selector = Selector::parse("img").ok()?;
+    let img = if element.value().name() == "img" {
+        element
+    } else {
+        element.select(&img_selector).next()?
+    };
+    let attrs = img.value();
+    attrs
+        .attr("src")
+        .or_else(|| attrs.attr("data-src"))
+        .filter(|src| !src.starts_with("data:"))
+        .or_else(|| {
+            // The last candidate of a srcset is usually the largest
+            attrs
+                .attr("srcset")?
+                .split(',')
+                .next_back()?
+                .split_whitespace()
+                .next()
+        })
+        .map(String::from)
+}
+
+/// The links in a paragraph that lead to another page.
+fn element_links(element: ElementRef, link_selector: &Selector, url: &str) -> Vec<Link> {
+    element
+        .select(link_selector)
+        .filter_map(|link| {
+            let href = link.value().attr("href")?;
+            let text = util::element_to_text(link);
+            let leads_away = href.starts_with("http") || href.starts_with('/');
+            (leads_away && !text.trim().is_empty()).then(|| Link {
+                text,
+                href: absolute_url(href, url),
+            })
+        })
+        .collect()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::profile::SiteProfile;
+
+    #[test]
+    fn test_parse_media() {
+        let body = r#"<html><head><script type="application/ld+json">
+            {"@context": "https://schema.org", "@graph": [
+                {"@type": "NewsArticle", "headline": "Storm"},
+                {"@type": "VideoObject", "name": "Beelden van de storm", "duration": "PT1M30S", "contentUrl": "https://cdn.example/storm.m3u8"}
+            ]}
+        </script></head><body><main><div>
+            <p>Het waait.</p>
+            <audio src="/audio/podcast.mp3" title="Podcast" data-duration="600"></audio>
+        </div></main></body></html>"#;
+        let document = Html::parse_document(body);
+        let profile = SiteProfile::default().nos;
+
+        let blocks =
+            parse_blocks(&document, "https://nos.nl/artikel/5", &(&profile).into()).unwrap();
+        let text: Vec<String> = blocks.iter().map(Block::to_string).collect();
+        assert_eq!(
+            text,
+            [
+                "[Video: Beelden van de storm (1:30)]",
+                "Het waait.",
+                "[Audio: Podcast (10:00)]"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_links() {
+        let document = Html::parse_document(
+            r#"<main><div><p>Lees ook <a href="/artikel/2-wind">dit artikel</a> en <a href="mailto:x@nos.nl">mail</a>.</p><p>Geen link.</p></div></main>"#,
+        );
+        let profile = SiteProfile::default().nos;
+
+        let blocks =
+            parse_blocks(&document, "https://nos.nl/artikel/1", &(&profile).into()).unwrap();
+        let Block::Linked { text, links } = &blocks[0] else {
+            panic!("The first paragraph has a link");
+        };
+        assert_eq!(text, "Lees ook dit artikel en mail.");
+        assert_eq!(links.len(), 1);
+        assert_eq!(links[0].text, "dit artikel");
+        assert_eq!(links[0].href, "https://nos.nl/artikel/2-wind");
+        assert!(matches!(&blocks[1], Block::Text(text) if text == "Geen link."));
+    }
+
+    #[test]
+    fn test_find_next_page() {
+        let url = "https://nos.nl/nieuws/binnenland";
+        let document = Html::parse_document(
+            r#"<html><head><link rel="next" href="?page=2"></head><body>
+            <a href="/nieuws">Volgende</a></body></html>"#,
+        );
+        assert_eq!(
+            find_next_page(&document, url).unwrap().as_deref(),
+            Some("https://nos.nl/nieuws/binnenland?page=2")
+        );
+
+        let document = Html::parse_document(r#"<a href="/nieuws">Volgende</a>"#);
+        assert_eq!(find_next_page(&document, url).unwrap(), None);
+    }
 
     #[test]
     fn test_get_article() {
         let url = "https://nos.nl/collectie/13995/artikel/2573968-thuisland-zwitserland-schakelt-ijsland-uit-op-ek-en-houdt-zicht-op-kwartfinales";
-        let result = get_article(url);
+        let result = get_article(url, &SiteProfile::default().nos);
 
         match result {
             Ok(all_text) => println!("Text: {}", all_text[0]),
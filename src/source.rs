@@ -0,0 +1,224 @@
+use std::collections::{HashMap, HashSet};
+
+use scraper::Html;
+
+use crate::{
+    archive::Archive,
+    liveblog::{self, Entry},
+    nos::{Backend, Nos},
+    profile::{LiveblogProfile, SiteProfile},
+    scrape::{Article, Block, Page},
+    sport::NosSport,
+};
+
+/// A news outlet that nos-cli can show articles from.
+pub trait Source {
+    /// Short name that identifies the source, e.g. `nos`
+    fn name(&self) -> &'static str;
+
+    /// Categories this source has listings for.
+    fn categories(&self) -> &[&'static str];
+
+    /// URL of the page a category's listing is scraped from.
+    fn category_url(&self, category: &str) -> String;
+
+    /// CSS selectors used on listing pages, checked by `doctor`.
+    fn listing_selectors(&self) -> Vec<String>;
+
+    /// CSS selectors used on article pages, checked by `doctor`.
+    fn article_selectors(&self) -> Vec<String>;
+
+    /// Fetches the first page of a category's listing.
+    fn get_listing(&self, category: &str) -> Result<Page, Box<dyn std::error::Error>>;
+
+    /// Fetches a further page of a listing, as linked from `Page::next_page`.
+    fn get_next_page(&self, url: &str, category: &str) -> Result<Page, Box<dyn std::error::Error>>;
+
+    /// Fetches the paragraphs and images of an article.
+    fn get_article(&self, url: &str) -> Result<Vec<Block>, Box<dyn std::error::Error>>;
+
+    /// Reads the articles from a listing page that was already fetched.
+    fn parse_listing(
+        &self,
+        document: &Html,
+        url: &str,
+        category: &str,
+    ) -> Result<Page, Box<dyn std::error::Error>>;
+
+    /// Reads the paragraphs and images from an article page that was already fetched.
+    fn parse_article(
+        &self,
+        document: &Html,
+        url: &str,
+    ) -> Result<Vec<Block>, Box<dyn std::error::Error>>;
+
+    /// URL of a category's RSS feed, for sources that have feeds.
+    fn feed_url(&self, _category: &str) -> Option<String> {
+        None
+    }
+
+    /// Whether listings are read from the feeds rather than the pages.
+    fn reads_feeds(&self) -> bool {
+        false
+    }
+}
+
+/// Articles of one or more categories, merged into one list.
+pub struct Listing {
+    pub articles: Vec<Article>,
+    /// Link to the next page for every category that has more articles
+    pub next_pages: HashMap<String, String>,
+}
+
+/// All available sources. Categories are looked up in each of them in order,
+/// the first source is used for categories none of them list.
+pub struct Sources {
+    sources: Vec<Box<dyn Source>>,
+    liveblog: LiveblogProfile,
+    /// Keeps everything that is fetched when enabled
+    archive: Option<Archive>,
+}
+
+impl Sources {
+    pub fn new(backend: Backend, profile: SiteProfile) -> Self {
+        Sources {
+            sources: vec![
+                Box::new(Nos::new(backend, profile.nos)),
+                Box::new(NosSport::new(profile.sport)),
+            ],
+            liveblog: profile.liveblog,
+            archive: None,
+        }
+    }
+
+    pub fn set_archive(&mut self, archive: Archive) {
+        self.archive = Some(archive);
+    }
+
+    pub fn archive(&self) -> Option<&Archive> {
+        self.archive.as_ref()
+    }
+
+    /// Stores a fetched listing in the archive. Failing to do so shouldn't
+    /// stop the articles from being shown, so errors are ignored.
+    fn archive_page(&self, page: &Page) {
+        if let Some(archive) = &self.archive {
+            let _ = archive.add_articles(&page.articles);
+        }
+    }
+
+    pub fn for_category(&self, category: &str) -> &dyn Source {
+        self.sources
+            .iter()
+            .find(|source| source.categories().contains(&category))
+            .unwrap_or(&self.sources[0])
+            .as_ref()
+    }
+
+    pub fn for_article(&self, article: &Article) -> &dyn Source {
+        self.sources
+            .iter()
+            .find(|source| source.name() == article.source)
+            .unwrap_or(&self.sources[0])
+            .as_ref()
+    }
+
+    /// Every source with its categories.
+    pub fn categories(&self) -> Vec<(&'static str, &[&'static str])> {
+        self.sources
+            .iter()
+            .map(|source| (source.name(), source.categories()))
+            .collect()
+    }
+
+    pub fn get_listing(&self, category: &str) -> Result<Page, Box<dyn std::error::Error>> {
+        let source = self.for_category(category);
+        let page = with_source(source.get_listing(category)?, source);
+        self.archive_page(&page);
+        Ok(page)
+    }
+
+    pub fn get_next_page(
+        &self,
+        url: &str,
+        category: &str,
+    ) -> Result<Page, Box<dyn std::error::Error>> {
+        let source = self.for_category(category);
+        let page = with_source(source.get_next_page(url, category)?, source);
+        self.archive_page(&page);
+        Ok(page)
+    }
+
+    /// Fetches the body of an article. With the archive enabled it's stored
+    /// there, and the archived body is used when the article can't be fetched.
+    pub fn get_article(&self, article: &Article) -> Result<Vec<Block>, Box<dyn std::error::Error>> {
+        let result = self.for_article(article).get_article(&article.href);
+        let Some(archive) = &self.archive else {
+            return result;
+        };
+        match result {
+            Ok(blocks) => {
+                let _ = archive.add_body(article, &blocks);
+                Ok(blocks)
+            }
+            Err(e) => archive.get_body(&article.href).ok().flatten().ok_or(e),
+        }
+    }
+
+    pub fn is_liveblog(&self, article: &Article) -> bool {
+        liveblog::is_liveblog(&article.href, &self.liveblog)
+    }
+
+    pub fn get_liveblog(&self, url: &str) -> Result<Vec<Entry>, Box<dyn std::error::Error>> {
+        liveblog::get_entries(url, &self.liveblog)
+    }
+
+    pub fn liveblog_selectors(&self) -> Vec<String> {
+        liveblog::selectors(&self.liveblog)
+    }
+
+    pub fn parse_liveblog(
+        &self,
+        document: &Html,
+    ) -> Result<Vec<Entry>, Box<dyn std::error::Error>> {
+        liveblog::parse_entries(document, &self.liveblog)
+    }
+
+    /// Fetches the listings of several categories and merges them into one list,
+    /// newest first. Articles that appear in more than one category are only kept once.
+    pub fn get_all_items(
+        &self,
+        categories: &[String],
+    ) -> Result<Listing, Box<dyn std::error::Error>> {
+        let mut seen_hrefs = HashSet::new();
+        let mut articles = Vec::new();
+        let mut next_pages = HashMap::new();
+
+        for category in categories {
+            let page = self.get_listing(category)?;
+            if let Some(next_page) = page.next_page {
+                next_pages.insert(category.clone(), next_page);
+            }
+            for article in page.articles {
+                if seen_hrefs.insert(article.href.clone()) {
+                    articles.push(article);
+                }
+            }
+        }
+
+        if categories.len() > 1 {
+            articles.sort_by_key(|a| std::cmp::Reverse(a.published));
+        }
+        Ok(Listing {
+            articles,
+            next_pages,
+        })
+    }
+}
+
+fn with_source(mut page: Page, source: &dyn Source) -> Page {
+    for article in &mut page.articles {
+        article.source = source.name().to_string();
+    }
+    page
+}
@@ -0,0 +1,253 @@
+use std::collections::HashSet;
+
+use scraper::{ElementRef, Html};
+
+use crate::{
+    profile::{self, SportProfile},
+    scrape::{self, Article, Block, BodySelectors, Page},
+    source::Source,
+    util,
+};
+
+/// The sport section of nos.nl.
+pub struct NosSport {
+    profile: SportProfile,
+}
+
+impl NosSport {
+    pub fn new(profile: SportProfile) -> Self {
+        NosSport { profile }
+    }
+}
+
+impl Source for NosSport {
+    fn name(&self) -> &'static str {
+        "nos-sport"
+    }
+
+    fn categories(&self) -> &[&'static str] {
+        &[
+            "sport",
+            "voetbal",
+            "formule-1",
+            "schaatsen",
+            "wielrennen",
+            "tennis",
+            "darts",
+            "hockey",
+            "atletiek",
+            "zwemmen",
+        ]
+    }
+
+    /// `sport` is the section's front page, every other category is a sport.
+    fn category_url(&self, category: &str) -> String {
+        match self.profile.pages.get(category) {
+            Some(url) => url.clone(),
+            None => profile::fill_pattern(&self.profile.category_url, category),
+        }
+    }
+
+    fn listing_selectors(&self) -> Vec<String> {
+        let link = &self.profile.link;
+        vec![
+            link.clone(),
+            scrape::nested_selector(link, &self.profile.title),
+            scrape::nested_selector(link, &self.profile.datetime),
+        ]
+    }
+
+    fn article_selectors(&self) -> Vec<String> {
+        vec![
+            self.profile.scoreboard.clone(),
+            self.profile.text.clone(),
+            self.profile.fallback_text.clone(),
+            self.profile.image.clone(),
+            self.profile.media.clone(),
+        ]
+    }
+
+    fn get_listing(&self, category: &str) -> Result<Page, Box<dyn std::error::Error>> {
+        self.get_next_page(&self.category_url(category), category)
+    }
+
+    fn get_next_page(&self, url: &str, category: &str) -> Result<Page, Box<dyn std::error::Error>> {
+        let body = reqwest::blocking::get(url)?.text()?;
+        self.parse_listing(&Html::parse_document(&body), url, category)
+    }
+
+    fn get_article(&self, url: &str) -> Result<Vec<Block>, Box<dyn std::error::Error>> {
+        let body = reqwest::blocking::get(url)?.text()?;
+        self.parse_article(&Html::parse_document(&body), url)
+    }
+
+    fn parse_listing(
+        &self,
+        document: &Html,
+        url: &str,
+        category: &str,
+    ) -> Result<Page, Box<dyn std::error::Error>> {
+        parse_listing(document, url, category, &self.profile)
+    }
+
+    fn parse_article(
+        &self,
+        document: &Html,
+        url: &str,
+    ) -> Result<Vec<Block>, Box<dyn std::error::Error>> {
+        parse_article(document, url, &self.profile)
+    }
+}
+
+/// Parses a sport index page. Unlike the news listings these mix featured
+/// blocks with plain lists, so every link to an article counts.
+fn parse_listing(
+    document: &Html,
+    url: &str,
+    category: &str,
+    profile: &SportProfile,
+) -> Result<Page, Box<dyn std::error::Error>> {
+    scrape::check_category_exists(document, category, &profile.not_found)?;
+
+    let link_selector = scrape::selector(&profile.link)?;
+    let title_selector = scrape::selector(&profile.title)?;
+    let datetime_selector = scrape::selector(&profile.datetime)?;
+
+    let mut seen_hrefs = HashSet::new();
+    let mut articles = Vec::new();
+
+    for link in document.select(&link_selector) {
+        let Some(href) = link.value().attr("href") else {
+            continue;
+        };
+        let href = scrape::absolute_url(href, url);
+        if !seen_hrefs.insert(href.clone()) {
+            continue;
+        }
+
+        let title = link
+            .select(&title_selector)
+            .next()
+            .map(util::element_to_text)
+            .unwrap_or_else(|| util::element_to_text(link));
+        let title = title.trim().to_string();
+        if title.is_empty() {
+            continue;
+        }
+
+        let time_element = link.select(&datetime_selector).next();
+        let datetime = time_element.map(util::element_to_text).unwrap_or_default();
+        let published = time_element
+            .and_then(|e| e.value().attr("datetime"))
+            .and_then(util::parse_timestamp);
+
+        articles.push(Article {
+            title,
+            href,
+            datetime,
+            published,
+            category: category.to_string(),
+            source: String::new(),
+        });
+    }
+
+    let next_page = scrape::find_next_page(document, url)?;
+
+    Ok(Page {
+        articles,
+        next_page,
+    })
+}
+
+/// Parses a sport article. Match reports start with a scoreboard, which is
+/// collapsed into a single line above the text.
+fn parse_article(
+    document: &Html,
+    url: &str,
+    profile: &SportProfile,
+) -> Result<Vec<Block>, Box<dyn std::error::Error>> {
+    let scoreboard_selector = scrape::selector(&profile.scoreboard)?;
+
+    let mut blocks = Vec::new();
+
+    if let Some(scoreboard) = document.select(&scoreboard_selector).next() {
+        blocks.push(Block::Text(collapse_whitespace(scoreboard)));
+    }
+
+    let mut selectors = BodySelectors::from(profile);
+    let mut body_blocks = scrape::parse_blocks(document, url, &selectors)?;
+    if !body_blocks
+        .iter()
+        .any(|b| matches!(b, Block::Text(_) | Block::Linked { .. }))
+    {
+        selectors.text = &profile.fallback_text;
+        body_blocks = scrape::parse_blocks(document, url, &selectors)?;
+    }
+    blocks.extend(body_blocks);
+
+    Ok(blocks)
+}
+
+fn collapse_whitespace(element: ElementRef) -> String {
+    element
+        .text()
+        .flat_map(str::split_whitespace)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::profile::SiteProfile;
+
+    #[test]
+    fn test_parse_listing() {
+        let body = r#"<html><body><main>
+            <a href="/artikel/1-ajax-wint"><h2>Ajax wint</h2><time datetime="2025-07-10T12:34:56+0200">12:34</time></a>
+            <ul>
+                <li><a href="/artikel/2-verstappen-op-pole">Verstappen op pole</a></li>
+                <li><a href="/artikel/1-ajax-wint">Ajax wint</a></li>
+                <li><a href="/video/3-samenvatting">Samenvatting</a></li>
+            </ul>
+        </main></body></html>"#;
+
+        let page = parse_listing(
+            &Html::parse_document(body),
+            "https://nos.nl/sport/voetbal",
+            "voetbal",
+            &SiteProfile::default().sport,
+        )
+        .unwrap();
+        let titles: Vec<&str> = page.articles.iter().map(|a| a.title.as_str()).collect();
+        assert_eq!(titles, ["Ajax wint", "Verstappen op pole"]);
+        assert_eq!(page.articles[0].href, "https://nos.nl/artikel/1-ajax-wint");
+        assert!(page.articles[0].published.is_some());
+        assert_eq!(page.articles[1].datetime, "");
+    }
+
+    #[test]
+    fn test_parse_match_report() {
+        let body = r#"<html><body><main>
+            <div class="match-scoreboard"><span>PSV</span> <span>2 - 1</span> <span>Feyenoord</span></div>
+            <div><p>PSV heeft de topper gewonnen.</p><figure><img src="/data/psv.jpg"><figcaption>Het eerste doelpunt</figcaption></figure><h2>Rust</h2></div>
+        </main></body></html>"#;
+
+        let url = "https://nos.nl/artikel/4-psv-wint";
+        let document = Html::parse_document(body);
+        let blocks = parse_article(&document, url, &SiteProfile::default().sport).unwrap();
+        let text: Vec<String> = blocks.iter().map(Block::to_string).collect();
+        assert_eq!(
+            text,
+            [
+                "PSV 2 - 1 Feyenoord",
+                "PSV heeft de topper gewonnen.",
+                "[Afbeelding: Het eerste doelpunt]",
+                "Rust"
+            ]
+        );
+        assert!(
+            matches!(&blocks[2], Block::Image(image) if image.url == "https://nos.nl/data/psv.jpg")
+        );
+    }
+}
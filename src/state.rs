@@ -1,58 +1,270 @@
+use std::{
+    collections::{HashMap, HashSet},
+    rc::Rc,
+};
+
+use chrono::Local;
 use rand::Rng;
 use regex::Regex;
+use termion::style;
 
 use crate::{
-    scrape::{self, Article},
-    util,
+    archive::Query,
+    export::Document,
+    graphics::{Graphic, ImageLoader, ImageProtocol},
+    liveblog::{self, Entry},
+    renderer::TitleStyle,
+    scrape::{Article, Block, Image, Media},
+    source::Sources,
+    store::{Bookmark, Bookmarks, ReadHistory},
+    util::{self, TimeFormat},
+    watchlist::Watchlist,
 };
 
+/// Most articles shown for an `:archive` search.
+const ARCHIVE_LIMIT: usize = 1000;
+
+/// Most articles open at once, opening another closes the least recently used.
+const MAX_TABS: usize = 10;
+
+/// Whether the list and the article share the screen.
+#[derive(Clone, Copy, Debug, PartialEq, clap::ValueEnum)]
+pub enum Layout {
+    /// The list or the article
+    Single,
+    /// The list on the left and the selected article on the right
+    Split,
+    /// Split when the terminal is at least 120 columns wide
+    Auto,
+}
+
+/// Narrowest terminal `Layout::Auto` splits.
+const AUTO_SPLIT_WIDTH: usize = 120;
+
+/// Which articles the list shows.
+enum ListView {
+    Articles,
+    Bookmarks,
+    /// Results of an archive search
+    Archive(Vec<Article>),
+    /// Articles that match the watchlist
+    Watch,
+    /// Articles open in a tab
+    Buffers,
+}
+
 #[derive(PartialEq)]
 pub enum Mode {
     Select,
     Article,
 }
 
+#[derive(Clone, Copy, PartialEq, clap::ValueEnum)]
+pub enum SortOrder {
+    /// Newest first
+    Time,
+    Title,
+    Category,
+    /// Unread first
+    Read,
+}
+
+/// An image in the article view that is drawn with an escape sequence.
+#[derive(Clone)]
+struct ArticleImage {
+    /// Index of its first line in `current_article_text`
+    line: usize,
+    rows: usize,
+    data: String,
+}
+
+/// The liveblog that is open, kept around to poll for new entries.
+#[derive(Clone)]
+struct Liveblog {
+    href: String,
+    title: String,
+    entries: Vec<Entry>,
+    /// Entries that appeared in the last poll
+    new_ids: HashSet<String>,
+}
+
+/// A fetched article that the preview pane can show again without fetching it.
+#[derive(Clone)]
+enum Body {
+    Blocks(Vec<Block>),
+    Liveblog(Vec<Entry>),
+}
+
+/// An opened article, kept as it was left so switching back to it doesn't
+/// fetch it again or lose the scroll position.
+#[derive(Clone)]
+struct Tab {
+    document: Document,
+    liveblog: Option<Liveblog>,
+    row_offset: usize,
+    /// Value of `State::tab_uses` when it was last shown
+    last_used: usize,
+}
+
 pub struct State {
     articles: Vec<Article>,
     all_articles: Vec<Article>,
+    sources: Rc<Sources>,
     titles: Vec<String>,
+    /// Index into `articles` for every row in `titles`, `None` for day separators
+    row_articles: Vec<Option<usize>>,
+    new_hrefs: HashSet<String>,
+    search_string: String,
+    read_history: ReadHistory,
+    hide_read: bool,
+    unread_only: bool,
+    bookmarks: Bookmarks,
+    view: ListView,
+    watchlist: Watchlist,
+    /// Articles matching the watchlist
+    watch_hits: HashSet<String>,
+    /// Articles that were checked against the watchlist
+    watch_checked: HashSet<String>,
+    /// Articles whose body couldn't be fetched, tried again after a refresh
+    watch_failed: HashSet<String>,
+    show_categories: bool,
+    time_format: TimeFormat,
+    sort_order: Option<SortOrder>,
+    group_by_day: bool,
+    /// Link to the next page of every category that has more articles
+    next_pages: HashMap<String, String>,
     selected_row: usize,
     row_offset: usize,
     pub mode: Mode, // TODO: use setter/getter
     current_article_text: Vec<String>,
+    /// The article that is open, with its body unless it's a liveblog
+    open_article: Option<Document>,
+    article_images: Vec<ArticleImage>,
+    /// Videos and audio in the open article, numbered from 1 in the text
+    article_media: Vec<Media>,
+    image_loader: ImageLoader,
+    liveblog: Option<Liveblog>,
+    /// Whether the article is shown next to the list
+    split: bool,
+    /// Part of the width taken by the list when split
+    split_ratio: f64,
+    /// Selected row and scroll offset of the list while an article is open
+    list_position: (usize, usize),
+    /// The article shown in the preview pane, also when it failed to load
+    preview_href: Option<String>,
+    /// Fetched bodies, so moving through the list or checking the watchlist
+    /// doesn't fetch them again
+    preview_cache: HashMap<String, Body>,
+    /// Articles that have been opened, the one in the article fields above
+    /// is `tabs[current_tab]` while in `Mode::Article`
+    tabs: Vec<Tab>,
+    current_tab: usize,
+    /// Counts the times a tab was shown, to close the least recently used one
+    tab_uses: usize,
+    /// Rows for the list or the article, without the tab bar
     term_height: usize, // TODO: maybe create trait to refresh this
+    screen_height: usize,
     term_width: usize,
 }
 
 impl State {
-    pub fn new(articles: Vec<Article>) -> Self {
+    pub fn new(
+        articles: Vec<Article>,
+        sources: Rc<Sources>,
+        read_history: ReadHistory,
+        bookmarks: Bookmarks,
+    ) -> Self {
         let all_articles = articles.clone();
+        let new_hrefs = HashSet::new();
 
-        let titles = util::articles_to_titles(&articles)
-            .into_iter()
-            .take(articles.len())
-            .collect::<Vec<String>>();
+        let (term_width, term_height) = termion::terminal_size().unwrap();
+        let term_height = term_height as usize - 1;
+        let term_width = term_width as usize;
+
+        let show_categories = has_multiple_categories(&articles);
+        let time_format = TimeFormat::default();
+        let titles = util::articles_to_titles(
+            &articles,
+            &new_hrefs,
+            show_categories,
+            time_format,
+            term_width,
+        );
+        let row_articles = (0..articles.len()).map(Some).collect();
+        let sort_order = None;
+        let group_by_day = false;
+        let next_pages = HashMap::new();
+        let search_string = String::new();
+        let hide_read = false;
+        let unread_only = false;
+        let view = ListView::Articles;
+        let watchlist = Watchlist::default();
+        let watch_hits = HashSet::new();
+        let watch_checked = HashSet::new();
+        let watch_failed = HashSet::new();
 
         let selected_row = 0;
         let row_offset = 0;
         let mode = Mode::Select;
 
         let current_article_text = Vec::new();
-
-        let (term_width, term_height) = termion::terminal_size().unwrap();
-        let term_height = term_height as usize - 1;
-        let term_width = term_width as usize;
+        let open_article = None;
+        let article_images = Vec::new();
+        let article_media = Vec::new();
+        let image_loader = ImageLoader::new(ImageProtocol::None);
+        let liveblog = None;
+        let split = false;
+        let split_ratio = 0.4;
+        let list_position = (0, 0);
+        let preview_href = None;
+        let preview_cache = HashMap::new();
+        let tabs = Vec::new();
+        let current_tab = 0;
+        let tab_uses = 0;
+        let screen_height = term_height;
 
         State {
             articles,
             all_articles,
+            sources,
             titles,
+            row_articles,
+            new_hrefs,
+            search_string,
+            read_history,
+            hide_read,
+            unread_only,
+            bookmarks,
+            view,
+            watchlist,
+            watch_hits,
+            watch_checked,
+            watch_failed,
+            show_categories,
+            time_format,
+            sort_order,
+            group_by_day,
+            next_pages,
             selected_row,
             row_offset,
             mode,
             current_article_text,
+            open_article,
+            article_images,
+            article_media,
+            image_loader,
+            liveblog,
+            split,
+            split_ratio,
+            list_position,
+            preview_href,
+            preview_cache,
+            tabs,
+            current_tab,
+            tab_uses,
             term_width,
             term_height,
+            screen_height,
         }
     }
 
@@ -66,6 +278,7 @@ impl State {
                 if self.selected_row + 1 == self.row_offset {
                     self.row_offset -= 1;
                 }
+                self.skip_separator(false);
             }
             Mode::Article => {
                 if self.row_offset == 0 {
@@ -79,13 +292,14 @@ impl State {
     pub fn move_down(&mut self) {
         match self.mode {
             Mode::Select => {
-                if self.selected_row + 1 >= self.articles.len() {
+                if self.selected_row + 1 >= self.titles.len() {
                     return;
                 }
                 self.selected_row += 1;
                 if self.selected_row - self.row_offset + 1 > self.term_height {
                     self.row_offset += 1;
                 }
+                self.skip_separator(true);
             }
             Mode::Article => {
                 if self.row_offset + self.term_height >= self.current_article_text.len() {
@@ -99,17 +313,24 @@ impl State {
     pub fn page_up(&mut self) {
         self.selected_row = self.selected_row.saturating_sub(self.term_height);
         self.row_offset = self.selected_row.saturating_sub(self.term_height - 1);
+        if self.mode == Mode::Select {
+            self.skip_separator(false);
+        }
     }
 
     pub fn page_down(&mut self) {
         match self.mode {
             Mode::Select => {
-                self.selected_row += self.term_height;
-                if self.selected_row > self.articles.len() {
-                    self.selected_row = self.articles.len() - 1;
+                if self.titles.is_empty() {
+                    return;
                 }
-                self.row_offset =
-                    std::cmp::min(self.selected_row, self.articles.len() - self.term_height);
+                self.selected_row =
+                    std::cmp::min(self.selected_row + self.term_height, self.titles.len() - 1);
+                self.row_offset = std::cmp::min(
+                    self.selected_row,
+                    self.titles.len().saturating_sub(self.term_height),
+                );
+                self.skip_separator(true);
             }
             Mode::Article => {
                 if self.row_offset + self.term_height >= self.current_article_text.len() {
@@ -127,14 +348,15 @@ impl State {
         self.row_offset = 0;
         if self.mode == Mode::Select {
             self.selected_row = 0;
+            self.skip_separator(true);
         }
     }
 
     pub fn go_bottom(&mut self) {
         match self.mode {
             Mode::Select => {
-                self.selected_row = self.articles.len() - 1;
-                self.row_offset = self.articles.len().saturating_sub(self.term_height);
+                self.selected_row = self.titles.len().saturating_sub(1);
+                self.row_offset = self.titles.len().saturating_sub(self.term_height);
             }
             Mode::Article => {
                 self.row_offset = self
@@ -145,97 +367,1315 @@ impl State {
         }
     }
 
-    pub fn enter_article(&mut self) {
+    pub fn half_page_up(&mut self) {
+        let half_page = std::cmp::max(self.term_height / 2, 1);
+        self.row_offset = self.row_offset.saturating_sub(half_page);
+        if self.mode == Mode::Select {
+            self.selected_row = self.selected_row.saturating_sub(half_page);
+            self.skip_separator(false);
+        }
+    }
+
+    pub fn half_page_down(&mut self) {
+        let half_page = std::cmp::max(self.term_height / 2, 1);
+        match self.mode {
+            Mode::Select => {
+                if self.titles.is_empty() {
+                    return;
+                }
+                self.selected_row =
+                    std::cmp::min(self.selected_row + half_page, self.titles.len() - 1);
+                self.row_offset = std::cmp::min(
+                    self.row_offset + half_page,
+                    self.titles.len().saturating_sub(self.term_height),
+                );
+                self.skip_separator(true);
+            }
+            Mode::Article => {
+                self.row_offset = std::cmp::min(
+                    self.row_offset + half_page,
+                    self.current_article_text
+                        .len()
+                        .saturating_sub(self.term_height),
+                );
+            }
+        }
+    }
+
+    /// Jumps to the given (zero-based) row, like `<count>G` in vim.
+    pub fn go_to_row(&mut self, row: usize) {
+        match self.mode {
+            Mode::Select => {
+                if self.titles.is_empty() {
+                    return;
+                }
+                self.selected_row = std::cmp::min(row, self.titles.len() - 1);
+                self.skip_separator(true);
+            }
+            Mode::Article => {
+                self.row_offset = std::cmp::min(
+                    row,
+                    self.current_article_text
+                        .len()
+                        .saturating_sub(self.term_height),
+                );
+            }
+        }
+    }
+
+    /// Scrolls so the selected row is in the middle of the screen (vim `zz`).
+    pub fn center_screen(&mut self) {
+        if self.mode == Mode::Select {
+            self.row_offset = self.selected_row.saturating_sub(self.term_height / 2);
+        }
+    }
+
+    /// Scrolls so the selected row is at the top of the screen (vim `zt`).
+    pub fn scroll_to_top(&mut self) {
+        if self.mode == Mode::Select {
+            self.row_offset = self.selected_row;
+        }
+    }
+
+    /// Scrolls so the selected row is at the bottom of the screen (vim `zb`).
+    pub fn scroll_to_bottom(&mut self) {
+        if self.mode == Mode::Select {
+            self.row_offset = (self.selected_row + 1).saturating_sub(self.term_height);
+        }
+    }
+
+    /// Selects the `n`th visible row from the top of the screen (vim `H`).
+    pub fn screen_top(&mut self, n: usize) {
+        if self.mode == Mode::Select && !self.titles.is_empty() {
+            let last_visible = self.last_visible_row();
+            self.selected_row = std::cmp::min(self.row_offset + n.saturating_sub(1), last_visible);
+            self.skip_separator(true);
+        }
+    }
+
+    /// Selects the row in the middle of the screen (vim `M`).
+    pub fn screen_middle(&mut self) {
+        if self.mode == Mode::Select && !self.titles.is_empty() {
+            let last_visible = self.last_visible_row();
+            self.selected_row = self.row_offset + (last_visible - self.row_offset) / 2;
+            self.skip_separator(true);
+        }
+    }
+
+    /// Selects the `n`th visible row from the bottom of the screen (vim `L`).
+    pub fn screen_bottom(&mut self, n: usize) {
+        if self.mode == Mode::Select && !self.titles.is_empty() {
+            let last_visible = self.last_visible_row();
+            self.selected_row = std::cmp::max(
+                last_visible.saturating_sub(n.saturating_sub(1)),
+                self.row_offset,
+            );
+            self.skip_separator(false);
+        }
+    }
+
+    /// Scrolls the view up, keeping the selected row on screen.
+    pub fn scroll_up(&mut self, lines: usize) {
+        self.row_offset = self.row_offset.saturating_sub(lines);
+        if self.mode == Mode::Select && self.selected_row >= self.row_offset + self.term_height {
+            self.selected_row = self.row_offset + self.term_height - 1;
+            self.skip_separator(false);
+        }
+    }
+
+    /// Scrolls the view down, keeping the selected row on screen.
+    pub fn scroll_down(&mut self, lines: usize) {
+        match self.mode {
+            Mode::Select => {
+                self.row_offset = std::cmp::min(
+                    self.row_offset + lines,
+                    self.titles.len().saturating_sub(self.term_height),
+                );
+                if self.selected_row < self.row_offset {
+                    self.selected_row = self.row_offset;
+                    self.skip_separator(true);
+                }
+            }
+            Mode::Article => {
+                self.row_offset = std::cmp::min(
+                    self.row_offset + lines,
+                    self.current_article_text
+                        .len()
+                        .saturating_sub(self.term_height),
+                );
+            }
+        }
+    }
+
+    /// Selects the article on the given (zero-based) screen row, if there is one.
+    pub fn select_visible_row(&mut self, row: usize) {
+        if self.mode == Mode::Select
+            && row < self.term_height
+            && matches!(self.row_articles.get(self.row_offset + row), Some(Some(_)))
+        {
+            self.selected_row = self.row_offset + row;
+        }
+    }
+
+    fn last_visible_row(&self) -> usize {
+        std::cmp::min(self.row_offset + self.term_height, self.titles.len()) - 1
+    }
+
+    pub fn selected_article(&self) -> Option<&Article> {
+        self.row_articles
+            .get(self.selected_row)
+            .copied()
+            .flatten()
+            .map(|i| &self.articles[i])
+    }
+
+    fn is_separator(&self, row: usize) -> bool {
+        matches!(self.row_articles.get(row), Some(None))
+    }
+
+    /// Moves the selection off a day separator, preferably in the given direction.
+    fn skip_separator(&mut self, forward: bool) {
+        if self.is_separator(self.selected_row) {
+            let after = (self.selected_row + 1..self.titles.len()).find(|&r| !self.is_separator(r));
+            let before = (0..self.selected_row)
+                .rev()
+                .find(|&r| !self.is_separator(r));
+            let row = if forward {
+                after.or(before)
+            } else {
+                before.or(after)
+            };
+            if let Some(row) = row {
+                self.selected_row = row;
+            }
+        }
+        self.scroll_to_selection();
+        // Keep the separator above the selection on screen
+        if self.row_offset == self.selected_row
+            && self.selected_row > 0
+            && self.is_separator(self.selected_row - 1)
+        {
+            self.row_offset -= 1;
+        }
+    }
+
+    /// Adjusts the row offset so the selected row is on screen.
+    fn scroll_to_selection(&mut self) {
+        if self.selected_row < self.row_offset {
+            self.row_offset = self.selected_row;
+        } else if self.selected_row >= self.row_offset + self.term_height {
+            self.row_offset = self.selected_row + 1 - self.term_height;
+        }
+    }
+
+    /// Opens the selected article. When it can't be fetched, the list or the
+    /// article that was open stays as it was.
+    pub fn enter_article(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        let Some(article) = self.selected_article().cloned() else {
+            return Ok(());
+        };
+        self.save_tab();
+        // Already open, continue where it was left
+        let open_tab = self
+            .tabs
+            .iter()
+            .position(|tab| tab.document.article.href == article.href);
+        // A body the preview fetched comes from the cache
+        if open_tab.is_none() {
+            self.load_article(&article, false)?;
+        }
+
+        if self.mode == Mode::Select {
+            self.list_position = (self.selected_row, self.row_offset);
+        }
+        self.mode = Mode::Article;
+        self.read_history.mark_read(&article.href);
+        if self.new_hrefs.remove(&article.href) {
+            self.update_titles();
+        }
+
+        if let Some(i) = open_tab {
+            self.show_tab(i);
+            return Ok(());
+        }
+        self.go_top();
+        if let Some(tab) = self.tab_from_article() {
+            if self.tabs.len() >= MAX_TABS
+                && let Some(oldest) = (0..self.tabs.len()).min_by_key(|&i| self.tabs[i].last_used)
+            {
+                self.tabs.remove(oldest);
+            }
+            self.tabs.push(tab);
+            self.current_tab = self.tabs.len() - 1;
+            self.update_height();
+        }
+        Ok(())
+    }
+
+    /// The open article as a tab.
+    fn tab_from_article(&mut self) -> Option<Tab> {
+        self.tab_uses += 1;
+        Some(Tab {
+            document: self.open_article.clone()?,
+            liveblog: self.liveblog.clone(),
+            row_offset: self.row_offset,
+            last_used: self.tab_uses,
+        })
+    }
+
+    /// Stores the open article in its tab before another one is shown.
+    fn save_tab(&mut self) {
+        if self.mode != Mode::Article {
+            return;
+        }
+        if let Some(tab) = self.tab_from_article()
+            && let Some(current) = self.tabs.get_mut(self.current_tab)
+        {
+            *current = tab;
+        }
+    }
+
+    /// Shows the article of a tab, formatted again from what was fetched.
+    fn show_tab(&mut self, i: usize) {
+        let Some(tab) = self.tabs.get_mut(i) else {
+            return;
+        };
+        self.tab_uses += 1;
+        tab.last_used = self.tab_uses;
+        let tab = tab.clone();
+        self.current_tab = i;
+        self.preview_href = Some(tab.document.article.href.clone());
+        self.open_article = Some(tab.document);
+        self.liveblog = tab.liveblog;
+        self.row_offset = tab.row_offset;
+        self.reformat();
+    }
+
+    /// Switches to the tab with the given index, counting from 0.
+    pub fn go_to_tab(&mut self, i: usize) {
+        if i >= self.tabs.len() {
+            return;
+        }
+        match self.mode {
+            Mode::Select => self.list_position = (self.selected_row, self.row_offset),
+            Mode::Article => self.save_tab(),
+        }
         self.mode = Mode::Article;
+        self.show_tab(i);
+    }
 
-        let url = self.articles[self.selected_row].href.as_str();
-        let raw_article_text =
-            scrape::get_article(url).expect("Request for getting the article failed.");
+    /// Moves `steps` tabs to the right, or to the left when not `forward`.
+    /// From the list it goes back to the tab that was open last.
+    pub fn next_tab(&mut self, forward: bool, steps: usize) {
+        let len = self.tabs.len();
+        if len == 0 {
+            return;
+        }
+        let i = match self.mode {
+            Mode::Select => self.current_tab,
+            Mode::Article if forward => (self.current_tab + steps) % len,
+            Mode::Article => (self.current_tab + len - steps % len) % len,
+        };
+        self.go_to_tab(i);
+    }
+
+    /// Closes the open tab and shows the next one, or the list after the last.
+    pub fn close_tab(&mut self) {
+        if self.mode != Mode::Article || self.current_tab >= self.tabs.len() {
+            return;
+        }
+        self.tabs.remove(self.current_tab);
+        self.update_height();
+        if self.tabs.is_empty() {
+            self.current_tab = 0;
+            self.leave_article();
+            return;
+        }
+        self.show_tab(self.current_tab.min(self.tabs.len() - 1));
+    }
+
+    /// Titles of the open tabs, with the index of the current one.
+    pub fn tab_titles(&self) -> (Vec<String>, usize) {
+        let titles = self
+            .tabs
+            .iter()
+            .map(|tab| tab.document.article.title.clone())
+            .collect();
+        (titles, self.current_tab)
+    }
+
+    /// Rows the tab bar takes, it's only shown with more than one tab.
+    pub fn tab_bar_rows(&self) -> usize {
+        if self.tabs.len() > 1 { 1 } else { 0 }
+    }
+
+    fn update_height(&mut self) {
+        self.term_height = self.screen_height - self.tab_bar_rows();
+    }
+
+    /// Whether the preview pane doesn't show the selected article yet.
+    pub fn preview_pending(&self) -> bool {
+        self.mode == Mode::Select
+            && self.is_split()
+            && self
+                .selected_article()
+                .is_some_and(|article| self.preview_href.as_ref() != Some(&article.href))
+    }
+
+    /// Shows the selected article in the preview pane when it was fetched
+    /// before. Otherwise it says it's loading, until `load_preview` is called
+    /// once moving through the list has paused.
+    pub fn update_preview(&mut self) {
+        if !self.preview_pending() {
+            return;
+        }
+        let cached = self
+            .selected_article()
+            .is_some_and(|article| self.preview_cache.contains_key(&article.href));
+        if cached {
+            self.load_preview();
+            return;
+        }
+        self.preview_href = None;
+        self.open_article = None;
+        self.liveblog = None;
+        self.article_images.clear();
+        self.article_media.clear();
+        self.current_article_text = vec![String::from("Loading...")];
+    }
+
+    /// Fetches the selected article for the preview pane.
+    pub fn load_preview(&mut self) {
+        if !self.preview_pending() {
+            return;
+        }
+        let Some(article) = self.selected_article().cloned() else {
+            return;
+        };
+        if let Err(e) = self.load_article(&article, true) {
+            self.clear_article(&article.href);
+            self.open_article = None;
+            self.current_article_text = vec![format!("Couldn't load the article: {}", e)];
+        }
+    }
+
+    /// Forgets the article that was shown, before showing the one at `href`.
+    fn clear_article(&mut self, href: &str) {
+        self.preview_href = Some(href.to_string());
+        self.article_images.clear();
+        self.article_media.clear();
+        self.liveblog = None;
+    }
+
+    /// Fetches an article and formats it as `current_article_text`. Nothing
+    /// changes when it can't be fetched. A `preview` leaves out the images and
+    /// may show a liveblog as it was fetched before, opening it gets it again.
+    fn load_article(
+        &mut self,
+        article: &Article,
+        preview: bool,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let article = article.clone();
+        let cached = self
+            .preview_cache
+            .get(&article.href)
+            .filter(|body| preview || matches!(body, Body::Blocks(_)))
+            .cloned();
+        let body = match cached {
+            Some(body) => body,
+            None if self.sources.is_liveblog(&article) => {
+                Body::Liveblog(self.sources.get_liveblog(&article.href)?)
+            }
+            None => Body::Blocks(self.fetch_body(&article)?),
+        };
+        self.clear_article(&article.href);
+        if self.is_split() {
+            self.preview_cache
+                .insert(article.href.clone(), body.clone());
+        }
+
+        let blocks = match body {
+            Body::Blocks(blocks) => blocks,
+            Body::Liveblog(entries) => {
+                self.show_liveblog(article, entries);
+                return Ok(());
+            }
+        };
+        self.open_article = Some(Document { article, blocks });
+        self.format_article(!preview);
+        Ok(())
+    }
+
+    /// Formats the open article as `current_article_text`, with its images
+    /// when `images` is set and as far as they are rendered yet.
+    fn format_article(&mut self, images: bool) {
+        let Some(document) = self.open_article.clone() else {
+            return;
+        };
+        let width = self.article_width();
+        self.article_images.clear();
+        self.article_media.clear();
 
         let mut formatted_article_text: Vec<String> = Vec::new();
-        for line in textwrap::wrap(
-            &self.articles[self.selected_row].title.clone(),
-            self.term_width,
-        ) {
+        for line in textwrap::wrap(&document.article.title, width) {
             formatted_article_text.push(line.to_string());
         }
 
-        for text in raw_article_text {
-            let wrapped_text = textwrap::wrap(&text, self.term_width);
-            for line in wrapped_text {
-                formatted_article_text.push(format!("\r\n{}", line.to_string()));
+        for block in document.blocks {
+            match block {
+                Block::Text(text) | Block::Linked { text, .. } => {
+                    let wrapped_text = textwrap::wrap(&text, width);
+                    for line in wrapped_text {
+                        formatted_article_text.push(format!("\r\n{}", line));
+                    }
+                }
+                Block::Heading { heading } => {
+                    for line in textwrap::wrap(&heading, width) {
+                        formatted_article_text.push(format!(
+                            "\r\n{}{}{}",
+                            style::Bold,
+                            line,
+                            style::Reset
+                        ));
+                    }
+                }
+                Block::Image(image) if !images => {
+                    formatted_article_text.push(format!("\r\n{}", Block::Image(image)));
+                }
+                Block::Image(image) => self.push_image(&mut formatted_article_text, image),
+                Block::Media(media) => {
+                    formatted_article_text.push(format!(
+                        "\r\n{}▶ [{}] {}{}",
+                        style::Bold,
+                        self.article_media.len() + 1,
+                        media,
+                        style::Reset
+                    ));
+                    self.article_media.push(media);
+                }
             }
             formatted_article_text.push("\r\n".to_string())
         }
 
         self.current_article_text = formatted_article_text;
+    }
 
-        self.go_top();
+    /// Formats the open article or liveblog again, for a new width or images
+    /// that came in.
+    fn reformat(&mut self) {
+        self.article_images.clear();
+        self.article_media.clear();
+        if self.liveblog.is_some() {
+            self.format_liveblog();
+        } else {
+            self.format_article(true);
+        }
+    }
+
+    fn show_liveblog(&mut self, article: Article, entries: Vec<Entry>) {
+        self.liveblog = Some(Liveblog {
+            href: article.href.clone(),
+            title: article.title.clone(),
+            entries,
+            new_ids: HashSet::new(),
+        });
+        self.open_article = Some(Document {
+            article,
+            blocks: Vec::new(),
+        });
+        self.format_liveblog();
+    }
+
+    /// Adds an image to the article text, as colored text or as blank lines
+    /// that the image is drawn over, followed by its caption. Until it's
+    /// rendered, or when it can't be, only the placeholder is added.
+    fn push_image(&mut self, lines: &mut Vec<String>, image: Image) {
+        let placeholder = Block::Image(image.clone()).to_string();
+        let max_rows = self.term_height / 2;
+        let width = self.article_width();
+
+        let graphic = self
+            .image_loader
+            .get(&image.url, width, max_rows)
+            .flatten()
+            .cloned();
+        match graphic {
+            Some(Graphic::Lines(rows)) => {
+                lines.extend(rows.into_iter().map(|row| format!("\r\n{}", row)));
+            }
+            Some(Graphic::Escape { data, rows }) => {
+                self.article_images.push(ArticleImage {
+                    line: lines.len(),
+                    rows,
+                    data,
+                });
+                // Shown while the image is only partly on screen
+                lines.push(format!("\r\n{}", placeholder));
+                lines.extend((1..rows).map(|_| String::from("\r\n")));
+            }
+            None => {
+                lines.push(format!("\r\n{}", placeholder));
+                return;
+            }
+        }
+
+        for line in textwrap::wrap(&image.caption, width) {
+            lines.push(format!("\r\n{}{}{}", style::Faint, line, style::Reset));
+        }
+    }
+
+    /// The `number`th video or audio fragment of the open article, counting from 1.
+    pub fn get_media(&self, number: usize) -> Option<&Media> {
+        if self.mode != Mode::Article {
+            return None;
+        }
+        self.article_media.get(number.checked_sub(1)?)
+    }
+
+    pub fn set_image_protocol(&mut self, image_protocol: ImageProtocol) {
+        self.image_loader = ImageLoader::new(image_protocol);
+    }
+
+    pub fn images_loading(&self) -> bool {
+        self.image_loader.is_loading()
+    }
+
+    /// Shows the images that were rendered since in the open article.
+    /// Returns whether it changed.
+    pub fn receive_images(&mut self) -> bool {
+        if !self.image_loader.receive() || self.mode != Mode::Article || self.liveblog.is_some() {
+            return false;
+        }
+        self.format_article(true);
+        true
+    }
+
+    /// Images that fit on the screen entirely, with the row in `get_subset`
+    /// they start at.
+    pub fn get_visible_images(&self) -> Vec<(usize, &str)> {
+        if self.mode != Mode::Article && !self.is_split() {
+            return Vec::new();
+        }
+        let offset = self.article_offset();
+        let visible_rows = self.get_article_subset().len();
+        self.article_images
+            .iter()
+            .filter(|image| image.line >= offset)
+            .map(|image| (image.line - offset, image))
+            // Leave a row below it, so drawing the image doesn't scroll the screen
+            .filter(|(row, image)| row + image.rows < visible_rows)
+            .map(|(row, image)| (row, image.data.as_str()))
+            .collect()
     }
 
     pub fn go_back(&mut self) {
+        if self.mode == Mode::Select && !matches!(self.view, ListView::Articles) {
+            self.set_view(ListView::Articles);
+            return;
+        }
         if !(self.mode == Mode::Article) {
             return;
         }
+        self.save_tab();
+        self.leave_article();
+    }
+
+    /// Returns to the list from the article, which stays open in its tab.
+    fn leave_article(&mut self) {
+        if self.is_split() {
+            // Keep the article in the preview and the list where it was
+            let href = self.open_article().map(|a| a.href.clone());
+            self.mode = Mode::Select;
+            (self.selected_row, self.row_offset) = self.list_position;
+            self.apply_filters();
+            self.reselect(href);
+            self.scroll_to_selection();
+            return;
+        }
         self.mode = Mode::Select;
+        self.liveblog = None;
+        self.open_article = None;
+        self.preview_href = None;
+        self.apply_filters();
         self.go_top();
     }
 
-    pub fn reset(&mut self) {
-        self.articles = self.all_articles.clone();
-        self.titles = util::articles_to_titles(&self.articles)
+    pub fn set_layout(&mut self, layout: Layout, ratio: f64) {
+        let split = match layout {
+            Layout::Single => false,
+            Layout::Split => true,
+            Layout::Auto => self.term_width >= AUTO_SPLIT_WIDTH,
+        };
+        self.split = split;
+        self.split_ratio = ratio;
+        self.relayout();
+    }
+
+    /// Switches between one pane and two.
+    pub fn toggle_split(&mut self) {
+        self.split = !self.split;
+        self.relayout();
+    }
+
+    /// Formats the list and the article again for the width they got.
+    fn relayout(&mut self) {
+        self.update_titles();
+        match self.mode {
+            Mode::Article => {
+                self.reformat();
+            }
+            Mode::Select => {
+                self.preview_href = None;
+                self.update_preview();
+            }
+        }
+    }
+
+    pub fn is_split(&self) -> bool {
+        self.split
+    }
+
+    /// Width of the list, the whole terminal unless split.
+    pub fn list_width(&self) -> usize {
+        if !self.split {
+            return self.term_width;
+        }
+        let width = (self.term_width as f64 * self.split_ratio).round() as usize;
+        // Leave both panes some room
+        width.clamp(20, self.term_width.saturating_sub(21).max(20))
+    }
+
+    /// Width of the article, leaving a column between the panes when split.
+    fn article_width(&self) -> usize {
+        if self.is_split() {
+            self.term_width.saturating_sub(self.list_width() + 1).max(1)
+        } else {
+            self.term_width
+        }
+    }
+
+    /// The stored body of a bookmarked article, otherwise the one online.
+    fn fetch_body(&self, article: &Article) -> Result<Vec<Block>, Box<dyn std::error::Error>> {
+        match self
+            .bookmarks
+            .get(&article.href)
+            .and_then(|b| b.body.clone())
+        {
+            Some(body) => Ok(body),
+            None => self.sources.get_article(article),
+        }
+    }
+
+    /// The open article, or the selected one in the list.
+    pub fn current_document(&self) -> Result<Document, Box<dyn std::error::Error>> {
+        if self.mode == Mode::Article
+            && let Some(document) = &self.open_article
+        {
+            let blocks = match &self.liveblog {
+                Some(liveblog) => liveblog::entries_to_blocks(&liveblog.entries),
+                None => document.blocks.clone(),
+            };
+            return Ok(Document {
+                article: document.article.clone(),
+                blocks,
+            });
+        }
+
+        let article = self
+            .selected_article()
+            .ok_or("No article selected")?
+            .clone();
+        let blocks = if self.sources.is_liveblog(&article) {
+            liveblog::entries_to_blocks(&self.sources.get_liveblog(&article.href)?)
+        } else {
+            self.fetch_body(&article)?
+        };
+        Ok(Document { article, blocks })
+    }
+
+    pub fn open_article(&self) -> Option<&Article> {
+        match self.mode {
+            Mode::Article => self.open_article.as_ref().map(|document| &document.article),
+            Mode::Select => None,
+        }
+    }
+
+    pub fn is_liveblog_open(&self) -> bool {
+        self.mode == Mode::Article && self.liveblog.is_some()
+    }
+
+    /// Fetches the open liveblog again and highlights the entries that are new
+    /// since the previous poll. Returns the number of new entries.
+    pub fn poll_liveblog(&mut self) -> Result<usize, Box<dyn std::error::Error>> {
+        let Some(liveblog) = &mut self.liveblog else {
+            return Ok(0);
+        };
+        let entries = self.sources.get_liveblog(&liveblog.href)?;
+
+        let known_ids: HashSet<&str> = liveblog.entries.iter().map(|e| e.id.as_str()).collect();
+        let new_ids: HashSet<String> = entries
+            .iter()
+            .filter(|e| !known_ids.contains(e.id.as_str()))
+            .map(|e| e.id.clone())
+            .collect();
+        let new_count = new_ids.len();
+        liveblog.entries = entries;
+        liveblog.new_ids = new_ids;
+
+        // New entries are added at the top, keep the text that is being read in place
+        let old_len = self.current_article_text.len();
+        self.format_liveblog();
+        if self.row_offset > 0 {
+            let added_lines = self.current_article_text.len().saturating_sub(old_len);
+            self.row_offset = std::cmp::min(
+                self.row_offset + added_lines,
+                self.current_article_text
+                    .len()
+                    .saturating_sub(self.term_height),
+            );
+        }
+        Ok(new_count)
+    }
+
+    fn format_liveblog(&mut self) {
+        let Some(liveblog) = &self.liveblog else {
+            return;
+        };
+        let width = self.article_width();
+        let mut lines: Vec<String> = textwrap::wrap(&liveblog.title, width)
             .into_iter()
-            .take(self.articles.len())
-            .collect::<Vec<String>>();
+            .map(|line| line.to_string())
+            .collect();
+        lines.extend(liveblog::entries_to_lines(
+            &liveblog.entries,
+            &liveblog.new_ids,
+            width,
+        ));
+        self.current_article_text = lines;
+    }
+
+    pub fn reset(&mut self) {
+        self.search_string.clear();
+        self.unread_only = false;
+        self.apply_filters();
         self.go_top();
     }
 
     pub fn filter_articles(&mut self, search_string: &str) -> Vec<String> {
         self.reset();
-        let re = Regex::new(search_string).unwrap_or(Regex::new("").unwrap());
+        self.search_string = search_string.to_string();
+        self.apply_filters();
+        self.get_subset().to_owned()
+    }
+
+    /// Only shows unread articles until the next reset.
+    pub fn filter_unread(&mut self) {
+        self.unread_only = true;
+        self.apply_filters();
+        self.go_top();
+    }
+
+    /// Toggles hiding read articles, which stays in effect across resets.
+    pub fn toggle_hide_read(&mut self) {
+        self.hide_read = !self.hide_read;
+        self.apply_filters();
+        self.go_top();
+    }
+
+    pub fn mark_all_read(&mut self) {
+        self.read_history
+            .mark_all_read(self.all_articles.iter().map(|a| a.href.as_str()));
+        self.new_hrefs.clear();
+        if self.hide_read || self.unread_only {
+            self.apply_filters();
+            self.go_top();
+        } else {
+            self.update_titles();
+        }
+    }
+
+    /// The open article, or the selected one in the list.
+    pub fn current_article(&self) -> Option<&Article> {
+        self.open_article().or_else(|| self.selected_article())
+    }
+
+    /// Bookmarks or unbookmarks the current article. Returns whether it is bookmarked now.
+    pub fn toggle_bookmark(&mut self, save_body: bool) -> std::io::Result<bool> {
+        let Some(article) = self.current_article().cloned() else {
+            return Ok(false);
+        };
+
+        if self.bookmarks.contains(&article.href) {
+            self.bookmarks.remove(&article.href)?;
+            if matches!(self.view, ListView::Bookmarks) && self.mode == Mode::Select {
+                self.apply_filters();
+                self.selected_row =
+                    std::cmp::min(self.selected_row, self.titles.len().saturating_sub(1));
+                self.skip_separator(false);
+            }
+            return Ok(false);
+        }
+
+        let body = if save_body {
+            self.sources.get_article(&article).ok()
+        } else {
+            None
+        };
+        self.bookmarks.add(Bookmark { article, body })?;
+        Ok(true)
+    }
+
+    /// Switches the list between the fetched articles and the bookmarks.
+    pub fn toggle_bookmark_view(&mut self) {
+        match self.view {
+            ListView::Bookmarks => self.set_view(ListView::Articles),
+            _ => self.set_view(ListView::Bookmarks),
+        }
+    }
+
+    /// Switches the list between the fetched articles and the watchlist hits.
+    pub fn toggle_watch_view(&mut self) {
+        match self.view {
+            ListView::Watch => self.set_view(ListView::Articles),
+            _ => self.set_view(ListView::Watch),
+        }
+    }
+
+    /// Switches the list between the fetched articles and the open tabs.
+    pub fn toggle_buffer_view(&mut self) {
+        match self.view {
+            ListView::Buffers => self.set_view(ListView::Articles),
+            _ => self.set_view(ListView::Buffers),
+        }
+    }
+
+    fn set_view(&mut self, view: ListView) {
+        self.save_tab();
+        self.view = view;
+        self.mode = Mode::Select;
+        self.apply_filters();
+        self.go_top();
+    }
+
+    /// Lists the archived articles matching `query`. Returns how many there are.
+    pub fn show_archive(&mut self, query: &Query) -> Result<usize, Box<dyn std::error::Error>> {
+        let archive = self
+            .sources
+            .archive()
+            .ok_or("The archive is off, start nos-cli with --archive")?;
+        let results = archive.search(query, ARCHIVE_LIMIT)?;
+        let count = results.len();
+        self.set_view(ListView::Archive(results));
+        Ok(count)
+    }
+
+    fn apply_filters(&mut self) {
+        let source = match &self.view {
+            ListView::Articles => self.all_articles.clone(),
+            ListView::Bookmarks => self.bookmarks.articles(),
+            ListView::Archive(results) => results.clone(),
+            ListView::Watch => self
+                .all_articles
+                .iter()
+                .filter(|a| self.watch_hits.contains(&a.href))
+                .cloned()
+                .collect(),
+            ListView::Buffers => self
+                .tabs
+                .iter()
+                .map(|tab| tab.document.article.clone())
+                .collect(),
+        };
+        self.show_categories = has_multiple_categories(&source);
+        let titles = util::articles_to_titles(
+            &source,
+            &self.new_hrefs,
+            self.show_categories,
+            self.time_format,
+            self.list_width(),
+        );
+        let re = Regex::new(&self.search_string).unwrap_or(Regex::new("").unwrap());
+        let skip_read = self.hide_read || self.unread_only;
 
         let mut matches: Vec<Article> = Vec::new();
-        for (i, title) in self.titles.iter().enumerate() {
+        for (i, title) in titles.iter().enumerate() {
+            let article = &source[i];
+            if skip_read && self.read_history.contains(&article.href) {
+                continue;
+            }
             if re.is_match(&title.to_lowercase()) {
-                matches.push(self.all_articles[i].clone());
+                matches.push(article.clone());
             }
         }
+        match self.sort_order {
+            Some(SortOrder::Time) => {
+                matches.sort_by_key(|a| std::cmp::Reverse(a.published));
+            }
+            Some(SortOrder::Title) => matches.sort_by_key(|a| a.title.to_lowercase()),
+            Some(SortOrder::Category) => matches.sort_by(|a, b| a.category.cmp(&b.category)),
+            Some(SortOrder::Read) => {
+                matches.sort_by_key(|a| self.read_history.contains(&a.href));
+            }
+            None => (),
+        }
+
         self.articles = matches;
-        self.titles = util::articles_to_titles(&self.articles)
-            .into_iter()
-            .take(self.articles.len())
-            .collect::<Vec<String>>();
+        self.update_titles();
+    }
 
-        self.get_subset().to_owned()
+    /// Sorts the list. Days only group a list sorted by time, so sorting
+    /// otherwise stops grouping. Returns whether it did.
+    pub fn set_sort_order(&mut self, sort_order: SortOrder) -> bool {
+        let ungroup = self.group_by_day && sort_order != SortOrder::Time;
+        if ungroup {
+            self.group_by_day = false;
+        }
+        self.sort_order = Some(sort_order);
+        self.apply_filters();
+        self.go_top();
+        ungroup
     }
 
-    pub fn get_subset(&self) -> &[String] {
-        let start_idx = self.row_offset;
+    /// Toggles separating the list into days, which sorts it by time.
+    pub fn toggle_group_by_day(&mut self) {
+        self.group_by_day = !self.group_by_day;
+        if self.group_by_day {
+            self.sort_order = Some(SortOrder::Time);
+            self.apply_filters();
+        } else {
+            self.update_titles();
+        }
+        self.go_top();
+    }
 
-        match self.mode {
-            Mode::Select => {
-                let end_idx = std::cmp::min(start_idx + self.term_height, self.articles.len());
-                return &self.titles[start_idx..end_idx];
+    /// Replaces the watchlist and checks the known articles against it.
+    pub fn set_watchlist(&mut self, watchlist: Watchlist) {
+        self.watchlist = watchlist;
+        self.watch_hits.clear();
+        self.watch_checked.clear();
+        self.check_watchlist();
+        self.apply_filters();
+    }
+
+    /// Checks the titles of the articles that haven't been checked yet against
+    /// the watchlist. Their bodies are left for `check_watchlist_body`.
+    fn check_watchlist(&mut self) {
+        self.watch_failed.clear();
+        if self.watchlist.is_empty() {
+            return;
+        }
+        for article in &self.all_articles {
+            if self.watch_checked.contains(&article.href) {
+                continue;
             }
-            Mode::Article => {
-                let end_idx = std::cmp::min(
-                    start_idx + self.term_height,
-                    self.current_article_text.len(),
-                );
-                return &self.current_article_text[start_idx..end_idx - 1];
+            if self.watchlist.matches(&article.title) {
+                self.watch_hits.insert(article.href.clone());
+            } else if self.watchlist.bodies {
+                continue;
+            }
+            self.watch_checked.insert(article.href.clone());
+        }
+    }
+
+    /// Whether there are articles left whose body should be checked against
+    /// the watchlist.
+    pub fn watchlist_pending(&self) -> bool {
+        self.next_watch_check().is_some()
+    }
+
+    fn next_watch_check(&self) -> Option<&Article> {
+        if self.watchlist.is_empty() || !self.watchlist.bodies {
+            return None;
+        }
+        self.all_articles.iter().find(|article| {
+            !self.watch_checked.contains(&article.href)
+                && !self.watch_failed.contains(&article.href)
+        })
+    }
+
+    /// Fetches the body of one article to check it against the watchlist, it's
+    /// only marked as checked once that worked. Returns whether it matched.
+    pub fn check_watchlist_body(&mut self) -> bool {
+        let Some(article) = self.next_watch_check().cloned() else {
+            return false;
+        };
+        let blocks = match self.preview_cache.get(&article.href) {
+            Some(Body::Blocks(blocks)) => Ok(blocks.clone()),
+            _ => self.fetch_body(&article),
+        };
+        let Ok(blocks) = blocks else {
+            self.watch_failed.insert(article.href);
+            return false;
+        };
+        let matched = self.watchlist.matches_body(&blocks);
+        self.preview_cache
+            .insert(article.href.clone(), Body::Blocks(blocks));
+        self.watch_checked.insert(article.href.clone());
+        if matched {
+            self.watch_hits.insert(article.href);
+            if matches!(self.view, ListView::Watch) {
+                self.apply_filters();
             }
         }
+        matched
+    }
+
+    /// Number of watchlist hits that haven't been read yet.
+    pub fn watch_alert_count(&self) -> usize {
+        self.watch_hits
+            .iter()
+            .filter(|href| !self.read_history.contains(href))
+            .count()
     }
 
+    /// Merges a freshly fetched listing into the known articles, optionally marking
+    /// the ones we haven't seen before as new. Returns the new articles.
+    pub fn merge_articles(&mut self, fetched: Vec<Article>, mark_new: bool) -> Vec<Article> {
+        let known_hrefs: HashSet<String> =
+            self.all_articles.iter().map(|a| a.href.clone()).collect();
+        let fetched_hrefs: HashSet<String> = fetched.iter().map(|a| a.href.clone()).collect();
+
+        let mut new_articles = Vec::new();
+        for article in &fetched {
+            if !known_hrefs.contains(&article.href) {
+                if mark_new {
+                    self.new_hrefs.insert(article.href.clone());
+                }
+                new_articles.push(article.clone());
+            }
+        }
+
+        let selected_href = self.selected_article().map(|a| a.href.clone());
+
+        // Keep articles that dropped off the listing at the bottom
+        let mut all_articles = fetched;
+        all_articles.extend(
+            self.all_articles
+                .drain(..)
+                .filter(|a| !fetched_hrefs.contains(&a.href)),
+        );
+        self.all_articles = all_articles;
+
+        self.check_watchlist();
+        self.apply_filters();
+        self.reselect(selected_href);
+
+        new_articles
+    }
+
+    /// Adds the links to further pages of categories we don't know yet.
+    pub fn add_next_pages(&mut self, next_pages: HashMap<String, String>) {
+        for (category, next_page) in next_pages {
+            self.next_pages.entry(category).or_insert(next_page);
+        }
+    }
+
+    /// Whether the last row is selected and older articles can be loaded.
+    pub fn can_load_more(&self) -> bool {
+        self.mode == Mode::Select
+            && matches!(self.view, ListView::Articles)
+            && !self.next_pages.is_empty()
+            && !self.articles.is_empty()
+            && self.selected_row + 1 >= self.titles.len()
+    }
+
+    /// Fetches the next page of every category and appends the articles on it.
+    /// Returns the number of articles that were added.
+    pub fn load_more(&mut self) -> Result<usize, Box<dyn std::error::Error>> {
+        let selected_href = self.selected_article().map(|a| a.href.clone());
+        let mut known_hrefs: HashSet<String> =
+            self.all_articles.iter().map(|a| a.href.clone()).collect();
+        let mut added = 0;
+        let mut error = None;
+
+        for (category, url) in self.next_pages.clone() {
+            let page = match self.sources.get_next_page(&url, &category) {
+                Ok(page) => page,
+                Err(e) => {
+                    // Reaching the bottom again shouldn't retry it on every key
+                    self.next_pages.remove(&category);
+                    error = Some(e);
+                    continue;
+                }
+            };
+            let mut added_from_page = 0;
+            for article in page.articles {
+                if known_hrefs.insert(article.href.clone()) {
+                    self.all_articles.push(article);
+                    added_from_page += 1;
+                }
+            }
+            added += added_from_page;
+
+            // Stop when a page has nothing new, so a broken link doesn't load forever
+            match page.next_page {
+                Some(next_page) if added_from_page > 0 => {
+                    self.next_pages.insert(category, next_page);
+                }
+                _ => {
+                    self.next_pages.remove(&category);
+                }
+            }
+        }
+
+        if has_multiple_categories(&self.all_articles) {
+            self.all_articles
+                .sort_by_key(|a| std::cmp::Reverse(a.published));
+        }
+        self.check_watchlist();
+        self.apply_filters();
+        self.reselect(selected_href);
+
+        match error {
+            Some(e) => Err(e),
+            None => Ok(added),
+        }
+    }
+
+    /// Selects the article with the given href again after the list changed.
+    fn reselect(&mut self, href: Option<String>) {
+        let new_row = href
+            .and_then(|href| self.articles.iter().position(|a| a.href == href))
+            .and_then(|i| self.row_articles.iter().position(|&row| row == Some(i)))
+            .unwrap_or(0);
+        if self.mode == Mode::Select {
+            self.row_offset = (self.row_offset + new_row).saturating_sub(self.selected_row);
+            self.selected_row = new_row;
+            self.skip_separator(true);
+        } else {
+            self.selected_row = new_row;
+        }
+    }
+
+    /// Styles for the titles currently on screen.
+    pub fn get_title_styles(&self) -> Vec<TitleStyle> {
+        let (_, row_offset) = self.list_position();
+        let end_idx = std::cmp::min(row_offset + self.term_height, self.titles.len());
+        self.row_articles[row_offset.min(end_idx)..end_idx]
+            .iter()
+            .map(|row| match row {
+                Some(i) => {
+                    let article = &self.articles[*i];
+                    TitleStyle {
+                        new: self.new_hrefs.contains(&article.href),
+                        read: self.read_history.contains(&article.href),
+                        bookmarked: self.bookmarks.contains(&article.href),
+                        watched: self.watch_hits.contains(&article.href),
+                        separator: false,
+                    }
+                }
+                None => TitleStyle {
+                    separator: true,
+                    ..Default::default()
+                },
+            })
+            .collect()
+    }
+
+    pub fn set_time_format(&mut self, time_format: TimeFormat) {
+        self.time_format = time_format;
+        self.update_titles();
+    }
+
+    pub fn get_time_format(&self) -> TimeFormat {
+        self.time_format
+    }
+
+    /// Formats the titles again, e.g. to update relative times.
+    pub fn update_titles(&mut self) {
+        let titles = util::articles_to_titles(
+            &self.articles,
+            &self.new_hrefs,
+            self.show_categories,
+            self.time_format,
+            self.list_width(),
+        );
+
+        self.titles = Vec::new();
+        self.row_articles = Vec::new();
+        let today = Local::now().date_naive();
+        let mut previous_day = None;
+        for (i, title) in titles.into_iter().enumerate() {
+            if self.group_by_day {
+                let day = self.articles[i]
+                    .published
+                    .map(|published| published.with_timezone(&Local).date_naive());
+                if i == 0 || day != previous_day {
+                    self.titles.push(util::day_label(day, today));
+                    self.row_articles.push(None);
+                    previous_day = day;
+                }
+            }
+            self.titles.push(title);
+            self.row_articles.push(Some(i));
+        }
+    }
+
+    pub fn get_subset(&self) -> &[String] {
+        match self.mode {
+            Mode::Select => self.get_list_subset(),
+            Mode::Article => self.get_article_subset(),
+        }
+    }
+
+    /// The titles on screen, also while the article next to them has focus.
+    pub fn get_list_subset(&self) -> &[String] {
+        let (_, start_idx) = self.list_position();
+        let end_idx = std::cmp::min(start_idx + self.term_height, self.titles.len());
+        &self.titles[start_idx.min(end_idx)..end_idx]
+    }
+
+    /// The lines of the article on screen, the top of it while it's a preview.
+    pub fn get_article_subset(&self) -> &[String] {
+        let start_idx = std::cmp::min(self.article_offset(), self.current_article_text.len());
+        let end_idx = std::cmp::min(
+            start_idx + self.term_height,
+            self.current_article_text.len(),
+        );
+        &self.current_article_text[start_idx..end_idx.saturating_sub(1).max(start_idx)]
+    }
+
+    /// The selected row relative to the top of the list on screen.
     pub fn get_relative_row(&self) -> usize {
-        self.selected_row - self.row_offset
+        let (selected_row, row_offset) = self.list_position();
+        selected_row.saturating_sub(row_offset)
+    }
+
+    /// Selected row and scroll offset of the list.
+    fn list_position(&self) -> (usize, usize) {
+        match self.mode {
+            Mode::Select => (self.selected_row, self.row_offset),
+            Mode::Article => self.list_position,
+        }
+    }
+
+    fn article_offset(&self) -> usize {
+        match self.mode {
+            Mode::Select => 0,
+            Mode::Article => self.row_offset,
+        }
     }
 
-    pub fn random_article(&mut self) {
-        if self.articles.len() == 0 {
+    pub fn random_article(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        if self.articles.is_empty() {
             self.reset();
         }
-        self.selected_row = rand::rng().random_range(0..self.articles.len());
-        self.enter_article();
+        if self.articles.is_empty() {
+            return Ok(());
+        }
+        let article_idx = rand::rng().random_range(0..self.articles.len());
+        self.selected_row = self
+            .row_articles
+            .iter()
+            .position(|&row| row == Some(article_idx))
+            .unwrap_or(0);
+        self.scroll_to_selection();
+        self.enter_article()
     }
 }
+
+fn has_multiple_categories(articles: &[Article]) -> bool {
+    articles
+        .iter()
+        .any(|article| article.category != articles[0].category)
+}
@@ -0,0 +1,234 @@
+use std::{
+    collections::HashSet,
+    fs::{self, OpenOptions},
+    io::{ErrorKind, Write},
+    path::PathBuf,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::scrape::{Article, Block};
+
+/// Directory where nos-cli keeps its data, e.g. `~/.local/share/nos-cli`.
+pub fn data_dir() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join("nos-cli"))
+}
+
+/// Directory where nos-cli looks for its configuration, e.g. `~/.config/nos-cli`.
+pub fn config_dir() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("nos-cli"))
+}
+
+/// Hrefs of the articles that have been opened, one per line in `read.txt`.
+pub struct ReadHistory {
+    path: Option<PathBuf>,
+    hrefs: HashSet<String>,
+}
+
+impl ReadHistory {
+    pub fn load() -> Self {
+        let path = data_dir().map(|dir| dir.join("read.txt"));
+        let hrefs = path
+            .as_ref()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .map(|contents| contents.lines().map(String::from).collect())
+            .unwrap_or_default();
+
+        ReadHistory { path, hrefs }
+    }
+
+    pub fn contains(&self, href: &str) -> bool {
+        self.hrefs.contains(href)
+    }
+
+    pub fn mark_read(&mut self, href: &str) {
+        self.mark_all_read([href]);
+    }
+
+    pub fn mark_all_read<'a>(&mut self, hrefs: impl IntoIterator<Item = &'a str>) {
+        let unread: Vec<&str> = hrefs
+            .into_iter()
+            .filter(|href| self.hrefs.insert(href.to_string()))
+            .collect();
+        if unread.is_empty() {
+            return;
+        }
+        // Failing to persist shouldn't interrupt reading, the history is kept in memory regardless
+        let _ = self.append(&unread);
+    }
+
+    fn append(&self, hrefs: &[&str]) -> std::io::Result<()> {
+        append_lines(self.path.as_ref(), hrefs)
+    }
+}
+
+/// Hrefs of the articles `nos-cli watch` has sent a notification for, one
+/// per line in `notified.txt`.
+pub struct Notified {
+    path: Option<PathBuf>,
+    hrefs: HashSet<String>,
+    /// Whether there was no file yet
+    new: bool,
+}
+
+impl Notified {
+    pub fn load() -> Self {
+        Self::load_at(data_dir().map(|dir| dir.join("notified.txt")))
+    }
+
+    pub fn load_at(path: Option<PathBuf>) -> Self {
+        let contents = path.as_ref().and_then(|path| fs::read_to_string(path).ok());
+        let new = contents.is_none();
+        let hrefs = contents
+            .map(|contents| contents.lines().map(String::from).collect())
+            .unwrap_or_default();
+
+        Notified { path, hrefs, new }
+    }
+
+    pub fn is_new(&self) -> bool {
+        self.new
+    }
+
+    pub fn contains(&self, href: &str) -> bool {
+        self.hrefs.contains(href)
+    }
+
+    pub fn add<'a>(&mut self, hrefs: impl IntoIterator<Item = &'a str>) -> std::io::Result<()> {
+        let added: Vec<&str> = hrefs
+            .into_iter()
+            .filter(|href| self.hrefs.insert(href.to_string()))
+            .collect();
+        // Written even when empty, so the next run knows it isn't the first
+        append_lines(self.path.as_ref(), &added)
+    }
+}
+
+/// Appends a line for every href to the file at `path`, creating it when needed.
+fn append_lines(path: Option<&PathBuf>, hrefs: &[&str]) -> std::io::Result<()> {
+    let Some(path) = path else {
+        return Ok(());
+    };
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    for href in hrefs {
+        writeln!(file, "{}", href)?;
+    }
+    Ok(())
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Bookmark {
+    #[serde(flatten)]
+    pub article: Article,
+    /// The article text at the time of bookmarking, so it can be read offline
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body: Option<Vec<Block>>,
+}
+
+/// Saved articles, stored as JSON in `bookmarks.json`.
+pub struct Bookmarks {
+    path: Option<PathBuf>,
+    bookmarks: Vec<Bookmark>,
+}
+
+impl Bookmarks {
+    /// Fails when there is a file that can't be read, rather than starting
+    /// without bookmarks and saving over it with the next change.
+    pub fn load() -> Result<Self, Box<dyn std::error::Error>> {
+        Self::load_at(data_dir().map(|dir| dir.join("bookmarks.json")))
+    }
+
+    fn load_at(path: Option<PathBuf>) -> Result<Self, Box<dyn std::error::Error>> {
+        let bookmarks = match &path {
+            Some(path) => match fs::read_to_string(path) {
+                Ok(contents) => serde_json::from_str(&contents)
+                    .map_err(|e| format!("Invalid bookmarks {}: {}", path.display(), e))?,
+                Err(e) if e.kind() == ErrorKind::NotFound => Vec::new(),
+                Err(e) => return Err(format!("Couldn't read {}: {}", path.display(), e).into()),
+            },
+            None => Vec::new(),
+        };
+
+        Ok(Bookmarks { path, bookmarks })
+    }
+
+    pub fn contains(&self, href: &str) -> bool {
+        self.get(href).is_some()
+    }
+
+    pub fn get(&self, href: &str) -> Option<&Bookmark> {
+        self.bookmarks.iter().find(|b| b.article.href == href)
+    }
+
+    pub fn articles(&self) -> Vec<Article> {
+        self.bookmarks.iter().map(|b| b.article.clone()).collect()
+    }
+
+    pub fn add(&mut self, bookmark: Bookmark) -> std::io::Result<()> {
+        self.bookmarks
+            .retain(|b| b.article.href != bookmark.article.href);
+        self.bookmarks.insert(0, bookmark);
+        self.save()
+    }
+
+    pub fn remove(&mut self, href: &str) -> std::io::Result<()> {
+        self.bookmarks.retain(|b| b.article.href != href);
+        self.save()
+    }
+
+    fn save(&self) -> std::io::Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(&self.bookmarks)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A path in the temp directory that doesn't exist yet.
+    fn temp_path(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("nos-cli-{}-{}", std::process::id(), name));
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    #[test]
+    fn test_invalid_bookmarks() {
+        let path = temp_path("bookmarks.json");
+        fs::write(&path, "[{\"title\": ").unwrap();
+        assert!(Bookmarks::load_at(Some(path.clone())).is_err());
+        fs::remove_file(&path).unwrap();
+        assert!(
+            Bookmarks::load_at(Some(path))
+                .unwrap()
+                .articles()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_notified() {
+        let path = temp_path("notified.txt");
+        let mut notified = Notified::load_at(Some(path.clone()));
+        assert!(notified.is_new());
+        notified.add([]).unwrap();
+        assert!(!Notified::load_at(Some(path.clone())).is_new());
+
+        notified.add(["a", "b", "a"]).unwrap();
+        let notified = Notified::load_at(Some(path.clone()));
+        assert!(!notified.is_new());
+        assert!(notified.contains("a") && notified.contains("b"));
+        assert!(!notified.contains("c"));
+        assert_eq!(fs::read_to_string(&path).unwrap(), "a\nb\n");
+        fs::remove_file(&path).unwrap();
+    }
+}
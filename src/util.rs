@@ -1,24 +1,228 @@
-use crate::scrape;
+use std::collections::HashSet;
+
+use chrono::{DateTime, Datelike, FixedOffset, Local, NaiveDate, Utc, Weekday};
+
+use crate::{renderer, scrape};
+
+/// How the publication time of an article is shown in the list.
+#[derive(Clone, Copy, Debug, Default, PartialEq, clap::ValueEnum)]
+pub enum TimeFormat {
+    /// The text shown on nos.nl, e.g. "12:34" or "gisteren"
+    #[default]
+    Original,
+    /// Time since publication, e.g. "5 min geleden"
+    Relative,
+    /// Date and time in the local timezone
+    Absolute,
+}
+
+/// Parses the `datetime` attribute of a `<time>` element, e.g. `2025-07-10T12:34:56+0200`.
+pub fn parse_timestamp(datetime: &str) -> Option<DateTime<FixedOffset>> {
+    DateTime::parse_from_rfc3339(datetime)
+        .or_else(|_| DateTime::parse_from_str(datetime, "%Y-%m-%dT%H:%M:%S%z"))
+        .ok()
+}
+
+pub fn format_time(article: &scrape::Article, time_format: TimeFormat) -> String {
+    match (time_format, article.published) {
+        (TimeFormat::Relative, Some(published)) => format_relative(published, Utc::now()),
+        (TimeFormat::Absolute, Some(published)) => format_absolute(published, Local::now()),
+        _ => article.datetime.clone(),
+    }
+}
+
+fn format_relative(published: DateTime<FixedOffset>, now: DateTime<Utc>) -> String {
+    let elapsed = now.signed_duration_since(published);
+    match elapsed.num_minutes() {
+        ..1 => String::from("zojuist"),
+        minutes @ 1..60 => format!("{} min geleden", minutes),
+        minutes @ 60..1440 => format!("{} uur geleden", minutes / 60),
+        1440..2880 => String::from("gisteren"),
+        minutes => format!("{} dagen geleden", minutes / 1440),
+    }
+}
+
+pub fn format_absolute(published: DateTime<FixedOffset>, now: DateTime<Local>) -> String {
+    let published = published.with_timezone(&Local);
+    if published.date_naive() == now.date_naive() {
+        published.format("%H:%M").to_string()
+    } else {
+        published.format("%d-%m-%Y %H:%M").to_string()
+    }
+}
+
+/// Parses an ISO 8601 duration like `PT1M30S` into seconds.
+pub fn parse_iso_duration(duration: &str) -> Option<u64> {
+    let time = duration.strip_prefix("PT")?;
+    let mut seconds = 0;
+    let mut number = String::new();
+    for c in time.chars() {
+        match c {
+            '0'..='9' | '.' => number.push(c),
+            'H' | 'M' | 'S' => {
+                let value = number.parse::<f64>().ok()? as u64;
+                seconds += match c {
+                    'H' => value * 3600,
+                    'M' => value * 60,
+                    _ => value,
+                };
+                number.clear();
+            }
+            _ => return None,
+        }
+    }
+    number.is_empty().then_some(seconds)
+}
+
+/// Formats a length in seconds like a media player does, e.g. "1:30" or "1:02:03".
+pub fn format_duration(seconds: u64) -> String {
+    let (hours, minutes, seconds) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+    if hours > 0 {
+        format!("{}:{:02}:{:02}", hours, minutes, seconds)
+    } else {
+        format!("{}:{:02}", minutes, seconds)
+    }
+}
 
 pub fn element_to_text(element: scraper::ElementRef) -> String {
-    return element.text().collect::<Vec<_>>().join("");
+    element.text().collect::<Vec<_>>().join("")
+}
+
+/// Label for the separator above the articles of a day, e.g. "Vandaag" or "Maandag".
+pub fn day_label(day: Option<NaiveDate>, today: NaiveDate) -> String {
+    let Some(day) = day else {
+        return String::from("Onbekend");
+    };
+    match (today - day).num_days() {
+        0 => String::from("Vandaag"),
+        1 => String::from("Gisteren"),
+        2..7 => String::from(match day.weekday() {
+            Weekday::Mon => "Maandag",
+            Weekday::Tue => "Dinsdag",
+            Weekday::Wed => "Woensdag",
+            Weekday::Thu => "Donderdag",
+            Weekday::Fri => "Vrijdag",
+            Weekday::Sat => "Zaterdag",
+            Weekday::Sun => "Zondag",
+        }),
+        _ => day.format("%d-%m-%Y").to_string(),
+    }
 }
 
-pub fn articles_to_titles(articles: &Vec<scrape::Article>) -> Vec<String> {
-    let (term_width, _) = termion::terminal_size().unwrap();
-    let term_width = term_width as usize;
+pub fn articles_to_titles(
+    articles: &Vec<scrape::Article>,
+    new_hrefs: &HashSet<String>,
+    show_category: bool,
+    time_format: TimeFormat,
+    width: usize,
+) -> Vec<String> {
     let mut titles = Vec::new();
 
     for article in articles {
-        let mut clipped_title = article.title.clone();
+        let datetime = format_time(article, time_format);
+        // Leave room for the badge the renderer puts in front of new articles
+        let badge_width = if new_hrefs.contains(&article.href) {
+            renderer::NEW_BADGE.len() + 1
+        } else {
+            0
+        };
+        let category_tag = if show_category {
+            format!("[{}] ", article.category)
+        } else {
+            String::new()
+        };
         // -6 because 3 dots + the space and parentheses below
-        clipped_title.truncate(term_width - article.datetime.len() - 6);
+        let max_chars = width.saturating_sub(
+            datetime.chars().count() + 6 + badge_width + category_tag.chars().count(),
+        );
+        let mut clipped_title: String = article.title.chars().take(max_chars).collect();
         if clipped_title.len() != article.title.len() {
             clipped_title.push_str("...");
         }
 
-        titles.push(format!("{} ({})", clipped_title, article.datetime));
+        titles.push(format!("{}{} ({})", category_tag, clipped_title, datetime));
+    }
+
+    titles
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_timestamp() {
+        let expected = DateTime::parse_from_rfc3339("2025-07-10T12:34:56+02:00").unwrap();
+        assert_eq!(parse_timestamp("2025-07-10T12:34:56+0200"), Some(expected));
+        assert_eq!(parse_timestamp("2025-07-10T12:34:56+02:00"), Some(expected));
+        assert_eq!(parse_timestamp("gisteren"), None);
     }
 
-    return titles;
+    #[test]
+    fn test_format_relative() {
+        let published = parse_timestamp("2025-07-10T12:00:00+02:00").unwrap();
+        let now = |time: &str| parse_timestamp(time).unwrap().with_timezone(&Utc);
+
+        assert_eq!(
+            format_relative(published, now("2025-07-10T12:00:30+02:00")),
+            "zojuist"
+        );
+        assert_eq!(
+            format_relative(published, now("2025-07-10T12:05:00+02:00")),
+            "5 min geleden"
+        );
+        assert_eq!(
+            format_relative(published, now("2025-07-10T15:30:00+02:00")),
+            "3 uur geleden"
+        );
+        assert_eq!(
+            format_relative(published, now("2025-07-11T13:00:00+02:00")),
+            "gisteren"
+        );
+        assert_eq!(
+            format_relative(published, now("2025-07-13T12:00:00+02:00")),
+            "3 dagen geleden"
+        );
+    }
+
+    #[test]
+    fn test_day_label() {
+        let today = NaiveDate::from_ymd_opt(2025, 7, 10).unwrap();
+        let day = |d| NaiveDate::from_ymd_opt(2025, 7, d);
+
+        assert_eq!(day_label(day(10), today), "Vandaag");
+        assert_eq!(day_label(day(9), today), "Gisteren");
+        assert_eq!(day_label(day(7), today), "Maandag");
+        assert_eq!(day_label(day(1), today), "01-07-2025");
+        assert_eq!(day_label(None, today), "Onbekend");
+    }
+
+    #[test]
+    fn test_durations() {
+        assert_eq!(parse_iso_duration("PT1M30S"), Some(90));
+        assert_eq!(parse_iso_duration("PT1H2M3.5S"), Some(3723));
+        assert_eq!(parse_iso_duration("1:30"), None);
+        assert_eq!(format_duration(90), "1:30");
+        assert_eq!(format_duration(3723), "1:02:03");
+    }
+
+    #[test]
+    fn test_clip_titles() {
+        let article = scrape::Article {
+            title: String::from("Één op de drie café's dicht"),
+            href: String::from("https://nos.nl/artikel/1-cafes"),
+            datetime: String::from("12:34"),
+            published: None,
+            category: String::from("binnenland"),
+            source: String::from("nos"),
+        };
+        let titles = articles_to_titles(
+            &vec![article],
+            &HashSet::new(),
+            false,
+            TimeFormat::Original,
+            24,
+        );
+        assert_eq!(titles, ["Één op de dri... (12:34)"]);
+    }
 }
@@ -0,0 +1,119 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use regex::{Regex, RegexBuilder};
+use serde::Deserialize;
+
+use crate::{scrape::Block, store};
+
+/// Topics to keep an eye on, read from `watchlist.toml` in the config directory.
+#[derive(Default)]
+pub struct Watchlist {
+    regexes: Vec<Regex>,
+    /// Whether article bodies are searched as well as titles
+    pub bodies: bool,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default)]
+struct WatchlistFile {
+    /// Words matched anywhere as a whole word
+    keywords: Vec<String>,
+    /// Regular expressions
+    patterns: Vec<String>,
+    bodies: bool,
+}
+
+impl Watchlist {
+    /// Loads the watchlist at `path`, or from `watchlist.toml` in the config
+    /// directory when no path is given. Without a file the watchlist is empty.
+    pub fn load(path: Option<&Path>) -> Result<Self, Box<dyn std::error::Error>> {
+        let path = match path {
+            Some(path) => path.to_path_buf(),
+            None => match default_path().filter(|path| path.exists()) {
+                Some(path) => path,
+                None => return Ok(Watchlist::default()),
+            },
+        };
+        let contents = fs::read_to_string(&path)
+            .map_err(|e| format!("Couldn't read {}: {}", path.display(), e))?;
+        let file: WatchlistFile = toml::from_str(&contents)
+            .map_err(|e| format!("Invalid watchlist {}: {}", path.display(), e))?;
+        Self::from_file(file)
+    }
+
+    fn from_file(file: WatchlistFile) -> Result<Self, Box<dyn std::error::Error>> {
+        let keywords = file.keywords.iter().map(|keyword| {
+            // Word boundaries only work next to letters and digits
+            let is_word = |c: char| c.is_alphanumeric() || c == '_';
+            let boundary = |c: Option<char>| if c.is_some_and(is_word) { r"\b" } else { "" };
+            format!(
+                "{}{}{}",
+                boundary(keyword.chars().next()),
+                regex::escape(keyword),
+                boundary(keyword.chars().last())
+            )
+        });
+        let regexes = keywords
+            .chain(file.patterns)
+            .map(|pattern| {
+                RegexBuilder::new(&pattern)
+                    .case_insensitive(true)
+                    .build()
+                    .map_err(|e| format!("Invalid watchlist pattern `{}`: {}", pattern, e))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Watchlist {
+            regexes,
+            bodies: file.bodies,
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.regexes.is_empty()
+    }
+
+    pub fn matches(&self, text: &str) -> bool {
+        self.regexes.iter().any(|regex| regex.is_match(text))
+    }
+
+    /// Whether any paragraph, heading or caption of an article matches.
+    pub fn matches_body(&self, blocks: &[Block]) -> bool {
+        blocks.iter().any(|block| match block {
+            Block::Text(text) | Block::Linked { text, .. } => self.matches(text),
+            Block::Heading { heading } => self.matches(heading),
+            Block::Image(image) => self.matches(&image.caption),
+            Block::Media(media) => self.matches(&media.title),
+        })
+    }
+}
+
+/// `~/.config/nos-cli/watchlist.toml`
+fn default_path() -> Option<PathBuf> {
+    store::config_dir().map(|dir| dir.join("watchlist.toml"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matches() {
+        let file = toml::from_str(
+            r#"
+            keywords = ["Utrecht", "C++"]
+            patterns = ['stikstof\w*']
+            "#,
+        )
+        .unwrap();
+        let watchlist = Watchlist::from_file(file).unwrap();
+        assert!(watchlist.matches("Brand in UTRECHT centrum"));
+        assert!(!watchlist.matches("Utrechtse wijk"));
+        assert!(watchlist.matches("Nieuwe versie van C++"));
+        assert!(watchlist.matches("Stikstofcrisis duurt voort"));
+        assert!(!watchlist.matches("Storm op komst"));
+        assert!(!watchlist.bodies);
+    }
+}